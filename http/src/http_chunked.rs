@@ -0,0 +1,256 @@
+use std::io::{self, Write};
+
+use crate::{
+    http_header::HeaderMap,
+    http_parser::MAX_HEAD_SIZE,
+    http_request::{process_header_line, ParseError},
+};
+
+//块大小行(包括块扩展)的长度上限
+const MAX_CHUNK_LINE: usize = 4096;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ChunkState {
+    Size,        //等待块大小行
+    Data(usize), //块数据还剩多少字节
+    DataEnd,     //块数据之后的\r\n
+    Trailer,     //最后一个块之后的尾部字段
+    Done,
+}
+
+//Transfer-Encoding: chunked 的增量解码器
+#[derive(Debug)]
+pub struct ChunkedDecoder {
+    state: ChunkState,
+    body: Vec<u8>,
+    trailers: HeaderMap,
+    trailer_len: usize, //尾部字段已经占用的字节数,和消息头共用大小上限
+}
+impl Default for ChunkedDecoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+impl ChunkedDecoder {
+    pub fn new() -> Self {
+        Self {
+            state: ChunkState::Size,
+            body: Vec::new(),
+            trailers: HeaderMap::new(),
+            trailer_len: 0,
+        }
+    }
+    //解码尽可能多的数据,返回消耗掉的字节数,不完整的行留到下次
+    pub fn decode(&mut self, buf: &[u8]) -> Result<usize, ParseError> {
+        let mut pos = 0;
+        loop {
+            match self.state {
+                ChunkState::Size => {
+                    let Some((line, used)) =
+                        next_line(&buf[pos..], MAX_CHUNK_LINE, chunk_line_too_long)?
+                    else {
+                        return Ok(pos);
+                    };
+                    pos += used;
+                    //忽略块扩展
+                    let size = line.split(';').next().unwrap_or("").trim();
+                    //from_str_radix会接受前导的+,块大小只允许十六进制数字
+                    let size = Some(size)
+                        .filter(|size| {
+                            !size.is_empty() && size.bytes().all(|b| b.is_ascii_hexdigit())
+                        })
+                        .and_then(|size| usize::from_str_radix(size, 16).ok())
+                        .ok_or_else(|| ParseError::InvalidChunk(line.to_string()))?;
+                    self.state = if size == 0 {
+                        ChunkState::Trailer
+                    } else {
+                        ChunkState::Data(size)
+                    };
+                }
+                ChunkState::Data(remaining) => {
+                    let available = (buf.len() - pos).min(remaining);
+                    self.body.extend_from_slice(&buf[pos..pos + available]);
+                    pos += available;
+                    if available < remaining {
+                        self.state = ChunkState::Data(remaining - available);
+                        return Ok(pos);
+                    }
+                    self.state = ChunkState::DataEnd;
+                }
+                ChunkState::DataEnd => {
+                    let Some((line, used)) =
+                        next_line(&buf[pos..], MAX_CHUNK_LINE, chunk_line_too_long)?
+                    else {
+                        return Ok(pos);
+                    };
+                    if !line.is_empty() {
+                        return Err(ParseError::InvalidChunk(line.to_string()));
+                    }
+                    pos += used;
+                    self.state = ChunkState::Size;
+                }
+                ChunkState::Trailer => {
+                    let max_len = MAX_HEAD_SIZE.saturating_sub(self.trailer_len);
+                    let Some((line, used)) = next_line(&buf[pos..], max_len, || {
+                        ParseError::HeadTooLarge(MAX_HEAD_SIZE)
+                    })?
+                    else {
+                        return Ok(pos);
+                    };
+                    pos += used;
+                    self.trailer_len += used;
+                    if line.is_empty() {
+                        self.state = ChunkState::Done;
+                    } else {
+                        let (key, val) = process_header_line(line)?;
+                        self.trailers.append(key, val);
+                    }
+                }
+                ChunkState::Done => return Ok(pos),
+            }
+        }
+    }
+    pub fn is_done(&self) -> bool {
+        self.state == ChunkState::Done
+    }
+    //已经解码出的请求体字节数
+    pub fn decoded_len(&self) -> usize {
+        self.body.len()
+    }
+    //解码完成后取出请求体和尾部字段
+    pub fn into_parts(self) -> (Vec<u8>, HeaderMap) {
+        (self.body, self.trailers)
+    }
+}
+//chunked编码写入器,每次write写出一个块,finish写出结束块
+pub struct ChunkedEncoder<W: Write> {
+    inner: W,
+}
+impl<W: Write> ChunkedEncoder<W> {
+    pub fn new(inner: W) -> Self {
+        Self { inner }
+    }
+    pub fn finish(mut self) -> io::Result<W> {
+        self.inner.write_all(b"0\r\n\r\n")?;
+        self.inner.flush()?;
+        Ok(self.inner)
+    }
+}
+impl<W: Write> Write for ChunkedEncoder<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        //空块会被当成结束块,不能写出去
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        write!(self.inner, "{:X}\r\n", buf.len())?;
+        self.inner.write_all(buf)?;
+        self.inner.write_all(b"\r\n")?;
+        Ok(buf.len())
+    }
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+fn chunk_line_too_long() -> ParseError {
+    ParseError::InvalidChunk(format!("line longer than {} bytes", MAX_CHUNK_LINE))
+}
+//取出完整的一行(去掉行尾的\r\n)和这一行占用的字节数
+//超过max_len还没有换行时返回too_long的错误,不再等待更多数据
+fn next_line(
+    buf: &[u8],
+    max_len: usize,
+    too_long: impl FnOnce() -> ParseError,
+) -> Result<Option<(&str, usize)>, ParseError> {
+    let Some(end) = buf.iter().take(max_len).position(|b| *b == b'\n') else {
+        if buf.len() >= max_len {
+            return Err(too_long());
+        }
+        return Ok(None);
+    };
+    let line = std::str::from_utf8(&buf[..end]).map_err(|_| ParseError::InvalidUtf8)?;
+    Ok(Some((line.strip_suffix('\r').unwrap_or(line), end + 1)))
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn test_decode_in_one_piece() {
+        let mut decoder = ChunkedDecoder::new();
+        let raw = b"5\r\nhello\r\n6;name=value\r\n world\r\n0\r\n\r\nextra";
+        let used = decoder.decode(raw).unwrap();
+        assert!(decoder.is_done());
+        assert_eq!(&raw[used..], b"extra");
+        let (body, trailers) = decoder.into_parts();
+        assert_eq!(body, b"hello world");
+        assert!(trailers.is_empty());
+    }
+    #[test]
+    fn test_decode_byte_by_byte() {
+        let raw = b"A\r\n0123456789\r\n0\r\nChecksum: abc\r\n\r\n";
+        let mut decoder = ChunkedDecoder::new();
+        let mut pending = Vec::new();
+        for b in raw {
+            pending.push(*b);
+            let used = decoder.decode(&pending).unwrap();
+            pending.drain(..used);
+        }
+        assert!(decoder.is_done());
+        let (body, trailers) = decoder.into_parts();
+        assert_eq!(body, b"0123456789");
+        assert_eq!(trailers.get("checksum"), Some("abc"));
+    }
+    #[test]
+    fn test_encode_round_trip() {
+        let mut encoder = ChunkedEncoder::new(Vec::new());
+        encoder.write_all(b"hello").unwrap();
+        encoder.write_all(b"").unwrap();
+        encoder.write_all(&[b'x'; 26]).unwrap();
+        let raw = encoder.finish().unwrap();
+        assert!(raw.starts_with(b"5\r\nhello\r\n1A\r\n"));
+        let mut decoder = ChunkedDecoder::new();
+        assert_eq!(decoder.decode(&raw).unwrap(), raw.len());
+        let (body, _) = decoder.into_parts();
+        assert_eq!(body.len(), 31);
+    }
+    #[test]
+    fn test_decode_errors() {
+        let mut decoder = ChunkedDecoder::new();
+        assert_eq!(
+            decoder.decode(b"zz\r\n"),
+            Err(ParseError::InvalidChunk("zz".into()))
+        );
+        let mut decoder = ChunkedDecoder::new();
+        assert_eq!(
+            decoder.decode(b"2\r\nabc\r\n"),
+            Err(ParseError::InvalidChunk("c".into()))
+        );
+        let mut decoder = ChunkedDecoder::new();
+        assert_eq!(
+            decoder.decode(b"+2\r\nab\r\n"),
+            Err(ParseError::InvalidChunk("+2".into()))
+        );
+    }
+    #[test]
+    fn test_decode_limits() {
+        //一直不结束的块大小行
+        let mut decoder = ChunkedDecoder::new();
+        let zeros = vec![b'0'; MAX_CHUNK_LINE];
+        assert_eq!(decoder.decode(&zeros[..100]), Ok(0));
+        assert!(matches!(
+            decoder.decode(&zeros),
+            Err(ParseError::InvalidChunk(_))
+        ));
+        //尾部字段总共不能超过消息头的上限
+        let mut decoder = ChunkedDecoder::new();
+        assert_eq!(decoder.decode(b"0\r\n"), Ok(3));
+        let trailer = b"X-Pad: aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa\r\n";
+        let mut result = Ok(0);
+        for _ in 0..MAX_HEAD_SIZE / trailer.len() + 1 {
+            result = decoder.decode(trailer);
+            if result.is_err() {
+                break;
+            }
+        }
+        assert_eq!(result, Err(ParseError::HeadTooLarge(MAX_HEAD_SIZE)));
+    }
+}
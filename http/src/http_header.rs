@@ -0,0 +1,122 @@
+//请求头/响应头集合:按名字大小写不敏感查找,同名字段保留多个值,并保持插入顺序
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HeaderMap {
+    entries: Vec<(String, String)>,
+}
+impl HeaderMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    //取第一个同名字段的值
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.entries
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+    //取所有同名字段的值
+    pub fn get_all<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a str> + 'a {
+        self.entries
+            .iter()
+            .filter(move |(k, _)| k.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+    //逗号分隔的字段(如Connection)中是否有指定的选项,不区分大小写
+    pub fn has_token(&self, name: &str, token: &str) -> bool {
+        self.get_all(name)
+            .flat_map(|value| value.split(','))
+            .any(|t| t.trim().eq_ignore_ascii_case(token))
+    }
+    pub fn contains_key(&self, name: &str) -> bool {
+        self.get(name).is_some()
+    }
+    //设置字段,已有的同名字段会被替换掉
+    pub fn insert(&mut self, name: impl Into<String>, value: impl Into<String>) {
+        let name = name.into();
+        self.remove(&name);
+        self.entries.push((name, value.into()));
+    }
+    //追加字段,不影响已有的同名字段
+    pub fn append(&mut self, name: impl Into<String>, value: impl Into<String>) {
+        self.entries.push((name.into(), value.into()));
+    }
+    //删除所有同名字段,返回第一个被删除的值
+    pub fn remove(&mut self, name: &str) -> Option<String> {
+        let mut removed = None;
+        self.entries.retain(|(k, v)| {
+            if !k.eq_ignore_ascii_case(name) {
+                return true;
+            }
+            if removed.is_none() {
+                removed = Some(v.clone());
+            }
+            false
+        });
+        removed
+    }
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.entries.iter().map(|(k, v)| (k.as_str(), v.as_str()))
+    }
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+impl<K: Into<String>, V: Into<String>> FromIterator<(K, V)> for HeaderMap {
+    fn from_iter<T: IntoIterator<Item = (K, V)>>(iter: T) -> Self {
+        let mut map = Self::new();
+        for (k, v) in iter {
+            map.append(k, v);
+        }
+        map
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn test_case_insensitive_get() {
+        let mut map = HeaderMap::new();
+        map.append("Content-Type", "text/html");
+        assert_eq!(map.get("content-type"), Some("text/html"));
+        assert_eq!(map.get("CONTENT-TYPE"), Some("text/html"));
+        assert!(map.contains_key("Content-type"));
+        assert_eq!(map.get("Accept"), None);
+    }
+    #[test]
+    fn test_repeated_fields() {
+        let mut map = HeaderMap::new();
+        map.append("Set-Cookie", "a=1");
+        map.append("set-cookie", "b=2");
+        assert_eq!(map.get("Set-Cookie"), Some("a=1"));
+        assert_eq!(
+            map.get_all("SET-COOKIE").collect::<Vec<_>>(),
+            ["a=1", "b=2"]
+        );
+        assert_eq!(map.len(), 2);
+    }
+    #[test]
+    fn test_has_token() {
+        let map: HeaderMap = [("Connection", "Upgrade, Keep-Alive"), ("connection", "TE")]
+            .into_iter()
+            .collect();
+        assert!(map.has_token("connection", "keep-alive"));
+        assert!(map.has_token("Connection", "te"));
+        assert!(!map.has_token("Connection", "close"));
+    }
+    #[test]
+    fn test_insert_replaces() {
+        let mut map: HeaderMap = [("Accept", "*/*"), ("accept", "text/html")]
+            .into_iter()
+            .collect();
+        map.insert("ACCEPT", "application/json");
+        assert_eq!(
+            map.get_all("Accept").collect::<Vec<_>>(),
+            ["application/json"]
+        );
+        assert_eq!(map.remove("accept"), Some("application/json".into()));
+        assert!(map.is_empty());
+    }
+}
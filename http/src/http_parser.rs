@@ -0,0 +1,678 @@
+use std::mem;
+
+use serde::de::DeserializeOwned;
+
+use crate::{
+    http_chunked::ChunkedDecoder,
+    http_header::HeaderMap,
+    http_request::{
+        find_head_end, process_header_line, split_line, trim_leading_newlines, HttpRequest, Method,
+        ParseError, Version,
+    },
+    http_response::{HttpResponse, StatusCode},
+};
+
+//消息头的大小上限,一直收不到空行时不能无限制地缓存
+pub(crate) const MAX_HEAD_SIZE: usize = 64 * 1024;
+//在缓冲区中找消息头的结尾,从上次扫描到的位置继续找,避免消息头慢慢到达时反复从头扫描
+fn scan_head(buf: &[u8], scanned: &mut usize) -> Result<Option<usize>, ParseError> {
+    //结束标记可能跨在上次扫描的边界上,往回多看3个字节
+    let start = scanned.saturating_sub(3);
+    match find_head_end(&buf[start..]) {
+        Some(end) if start + end > MAX_HEAD_SIZE => Err(ParseError::HeadTooLarge(MAX_HEAD_SIZE)),
+        Some(end) => {
+            *scanned = 0;
+            Ok(Some(start + end))
+        }
+        None if buf.len() > MAX_HEAD_SIZE => Err(ParseError::HeadTooLarge(MAX_HEAD_SIZE)),
+        None => {
+            *scanned = buf.len();
+            Ok(None)
+        }
+    }
+}
+#[derive(Debug)]
+enum ParseState {
+    Head,
+    Body {
+        request: Box<HttpRequest>,
+        content_length: usize,
+    },
+    Chunked {
+        request: Box<HttpRequest>,
+        decoder: ChunkedDecoder,
+    },
+}
+
+//增量式的请求解析器,数据按到达的顺序喂进来,解析出完整的请求后返回
+#[derive(Debug)]
+pub struct RequestParser {
+    buf: Vec<u8>,
+    scanned: usize, //缓冲区中已经找过消息头结尾的字节数
+    state: ParseState,
+    max_body_size: Option<usize>, //请求体的大小上限,超过时返回ContentTooLarge
+}
+impl Default for RequestParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+impl RequestParser {
+    pub fn new() -> Self {
+        Self {
+            buf: Vec::new(),
+            scanned: 0,
+            state: ParseState::Head,
+            max_body_size: None,
+        }
+    }
+    pub fn with_max_body_size(max_body_size: usize) -> Self {
+        Self {
+            max_body_size: Some(max_body_size),
+            ..Self::new()
+        }
+    }
+    //喂入新到达的数据,请求还不完整时返回Ok(None)
+    //一个请求之后多出来的数据留在缓冲区里,用feed(&[])继续解析下一个请求
+    pub fn feed(&mut self, data: &[u8]) -> Result<Option<HttpRequest>, ParseError> {
+        self.buf.extend_from_slice(data);
+        loop {
+            match mem::replace(&mut self.state, ParseState::Head) {
+                ParseState::Head => {
+                    let skipped = self.buf.len() - trim_leading_newlines(&self.buf).len();
+                    self.buf.drain(..skipped);
+                    self.scanned = self.scanned.saturating_sub(skipped);
+                    let Some(head_end) = scan_head(&self.buf, &mut self.scanned)? else {
+                        return Ok(None);
+                    };
+                    let request = Box::new(HttpRequest::try_from(&self.buf[..head_end])?);
+                    self.buf.drain(..head_end);
+                    self.state = if is_chunked(&request)? {
+                        ParseState::Chunked {
+                            request,
+                            decoder: ChunkedDecoder::new(),
+                        }
+                    } else {
+                        let content_length = content_length(&request.header)?;
+                        self.check_body_size(content_length)?;
+                        ParseState::Body {
+                            content_length,
+                            request,
+                        }
+                    };
+                }
+                ParseState::Body {
+                    mut request,
+                    content_length,
+                } => {
+                    if self.buf.len() < content_length {
+                        self.state = ParseState::Body {
+                            request,
+                            content_length,
+                        };
+                        return Ok(None);
+                    }
+                    request.body = self.buf.drain(..content_length).collect();
+                    return Ok(Some(*request));
+                }
+                ParseState::Chunked {
+                    mut request,
+                    mut decoder,
+                } => {
+                    let used = decoder.decode(&self.buf)?;
+                    self.buf.drain(..used);
+                    //chunked请求体事先不知道长度,边解码边检查
+                    self.check_body_size(decoder.decoded_len())?;
+                    if !decoder.is_done() {
+                        self.state = ParseState::Chunked { request, decoder };
+                        return Ok(None);
+                    }
+                    //尾部字段合并到请求头中
+                    let (body, trailers) = decoder.into_parts();
+                    for (key, val) in trailers.iter() {
+                        request.header.append(key, val);
+                    }
+                    request.body = body;
+                    return Ok(Some(*request));
+                }
+            }
+        }
+    }
+    //缓冲区里还没有被解析掉的字节数
+    pub fn buffered(&self) -> usize {
+        self.buf.len()
+    }
+    //请求头已经解析完、还在等请求体时返回请求头,用来处理Expect: 100-continue
+    pub fn head(&self) -> Option<&HttpRequest> {
+        match &self.state {
+            ParseState::Head => None,
+            ParseState::Body { request, .. } | ParseState::Chunked { request, .. } => Some(request),
+        }
+    }
+    fn check_body_size(&self, size: usize) -> Result<(), ParseError> {
+        match self.max_body_size {
+            Some(max) if size > max => Err(ParseError::ContentTooLarge(max)),
+            _ => Ok(()),
+        }
+    }
+}
+//请求体是否使用chunked编码,不认识的传输编码无法确定请求体长度
+fn is_chunked(request: &HttpRequest) -> Result<bool, ParseError> {
+    let codings = transfer_codings(&request.header);
+    let Some(last) = codings.last() else {
+        return Ok(false);
+    };
+    if !last.eq_ignore_ascii_case("chunked") {
+        return Err(ParseError::UnsupportedTransferEncoding(codings.join(", ")));
+    }
+    //同时带Content-Length的请求长度有歧义,直接拒绝
+    if request.header.contains_key("Content-Length") {
+        return Err(ParseError::BadHeader(
+            "Content-Length with Transfer-Encoding".into(),
+        ));
+    }
+    Ok(true)
+}
+//Transfer-Encoding中按顺序列出的传输编码,可能分散在多个头里
+fn transfer_codings(headers: &HeaderMap) -> Vec<&str> {
+    headers
+        .get_all("Transfer-Encoding")
+        .flat_map(|value| value.split(','))
+        .map(str::trim)
+        .filter(|coding| !coding.is_empty())
+        .collect()
+}
+fn content_length(headers: &HeaderMap) -> Result<usize, ParseError> {
+    Ok(parse_content_length(headers)?.unwrap_or(0))
+}
+fn parse_content_length(headers: &HeaderMap) -> Result<Option<usize>, ParseError> {
+    let mut length = None;
+    for value in headers.get_all("Content-Length") {
+        //parse会接受前导的+,Content-Length只允许十进制数字
+        let parsed = Some(value)
+            .filter(|value| !value.is_empty() && value.bytes().all(|b| b.is_ascii_digit()))
+            .and_then(|value| value.parse().ok())
+            .ok_or_else(|| ParseError::InvalidContentLength(value.to_string()))?;
+        //多个Content-Length的值不一致时无法确定请求体的长度
+        if length.is_some_and(|l| l != parsed) {
+            return Err(ParseError::InvalidContentLength(value.to_string()));
+        }
+        length = Some(parsed);
+    }
+    Ok(length)
+}
+//客户端解析出的响应
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParsedResponse {
+    pub version: Version,
+    pub status_code: StatusCode,
+    pub reason: String,
+    pub headers: HeaderMap,
+    pub body: Vec<u8>,
+}
+impl ParsedResponse {
+    //响应体按utf-8文本读取
+    pub fn text(&self) -> Result<&str, std::str::Utf8Error> {
+        std::str::from_utf8(&self.body)
+    }
+    //响应体按json反序列化
+    pub fn json<T: DeserializeOwned>(&self) -> serde_json::Result<T> {
+        serde_json::from_slice(&self.body)
+    }
+}
+impl From<ParsedResponse> for HttpResponse {
+    fn from(value: ParsedResponse) -> Self {
+        let mut builder = HttpResponse::builder().status(value.status_code);
+        for (k, v) in value.headers.iter() {
+            builder = builder.header(k, v);
+        }
+        builder.body(value.body)
+    }
+}
+#[derive(Debug)]
+enum ResponseState {
+    Head,
+    Body {
+        response: Box<ParsedResponse>,
+        content_length: usize,
+    },
+    Chunked {
+        response: Box<ParsedResponse>,
+        decoder: ChunkedDecoder,
+    },
+    UntilClose {
+        response: Box<ParsedResponse>,
+    },
+}
+//增量式的响应解析器,多出来的数据留给下一个响应
+#[derive(Debug)]
+pub struct ResponseParser {
+    buf: Vec<u8>,
+    scanned: usize,
+    state: ResponseState,
+    head_request: bool,
+}
+impl Default for ResponseParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+impl ResponseParser {
+    pub fn new() -> Self {
+        Self {
+            buf: Vec::new(),
+            scanned: 0,
+            state: ResponseState::Head,
+            head_request: false,
+        }
+    }
+    //HEAD请求的响应没有响应体,需要知道请求方法
+    pub fn for_method(method: &Method) -> Self {
+        Self {
+            head_request: *method == Method::HEAD,
+            ..Self::new()
+        }
+    }
+    //喂入新到达的数据,响应还不完整时返回Ok(None)
+    pub fn feed(&mut self, data: &[u8]) -> Result<Option<ParsedResponse>, ParseError> {
+        self.buf.extend_from_slice(data);
+        loop {
+            match mem::replace(&mut self.state, ResponseState::Head) {
+                ResponseState::Head => {
+                    let skipped = self.buf.len() - trim_leading_newlines(&self.buf).len();
+                    self.buf.drain(..skipped);
+                    self.scanned = self.scanned.saturating_sub(skipped);
+                    let Some(head_end) = scan_head(&self.buf, &mut self.scanned)? else {
+                        return Ok(None);
+                    };
+                    let response = Box::new(parse_response_head(&self.buf[..head_end])?);
+                    self.buf.drain(..head_end);
+                    let code = response.status_code;
+                    //101以外的1xx是临时响应,跳过继续等最终响应
+                    if code.is_informational() && code != StatusCode::SWITCHING_PROTOCOLS {
+                        continue;
+                    }
+                    self.state = self.body_state(response)?;
+                }
+                ResponseState::Body {
+                    mut response,
+                    content_length,
+                } => {
+                    if self.buf.len() < content_length {
+                        self.state = ResponseState::Body {
+                            response,
+                            content_length,
+                        };
+                        return Ok(None);
+                    }
+                    response.body = self.buf.drain(..content_length).collect();
+                    return Ok(Some(*response));
+                }
+                ResponseState::Chunked {
+                    mut response,
+                    mut decoder,
+                } => {
+                    let used = decoder.decode(&self.buf)?;
+                    self.buf.drain(..used);
+                    if !decoder.is_done() {
+                        self.state = ResponseState::Chunked { response, decoder };
+                        return Ok(None);
+                    }
+                    let (body, trailers) = decoder.into_parts();
+                    for (key, val) in trailers.iter() {
+                        response.headers.append(key, val);
+                    }
+                    response.body = body;
+                    return Ok(Some(*response));
+                }
+                //读到连接关闭为止,由finish返回
+                state @ ResponseState::UntilClose { .. } => {
+                    self.state = state;
+                    return Ok(None);
+                }
+            }
+        }
+    }
+    //连接关闭时调用,返回以关闭连接为结束的响应
+    pub fn finish(&mut self) -> Result<Option<ParsedResponse>, ParseError> {
+        match mem::replace(&mut self.state, ResponseState::Head) {
+            ResponseState::UntilClose { mut response } => {
+                response.body = mem::take(&mut self.buf);
+                Ok(Some(*response))
+            }
+            ResponseState::Head if trim_leading_newlines(&self.buf).is_empty() => Ok(None),
+            _ => Err(ParseError::UnexpectedEof),
+        }
+    }
+    //按RFC 9112 6.3 确定响应体的长度
+    fn body_state(&self, response: Box<ParsedResponse>) -> Result<ResponseState, ParseError> {
+        let code = response.status_code;
+        if self.head_request
+            || code.is_informational()
+            || code == StatusCode::NO_CONTENT
+            || code == StatusCode::NOT_MODIFIED
+        {
+            return Ok(ResponseState::Body {
+                response,
+                content_length: 0,
+            });
+        }
+        let codings = transfer_codings(&response.headers);
+        if let Some(last) = codings.last() {
+            return Ok(if last.eq_ignore_ascii_case("chunked") {
+                ResponseState::Chunked {
+                    response,
+                    decoder: ChunkedDecoder::new(),
+                }
+            } else {
+                ResponseState::UntilClose { response }
+            });
+        }
+        Ok(match parse_content_length(&response.headers)? {
+            Some(content_length) => ResponseState::Body {
+                response,
+                content_length,
+            },
+            None => ResponseState::UntilClose { response },
+        })
+    }
+}
+fn parse_response_head(head: &[u8]) -> Result<ParsedResponse, ParseError> {
+    let head = std::str::from_utf8(head).map_err(|_| ParseError::InvalidUtf8)?;
+    let (line, mut rest) = split_line(head);
+    let bad_line = || ParseError::BadStatusLine(line.to_string());
+    //状态行: HTTP/1.1 200 OK,原因短语可以为空
+    let (version, rest_line) = line.split_once(' ').ok_or_else(bad_line)?;
+    let (code, reason) = rest_line.split_once(' ').unwrap_or((rest_line, ""));
+    let parsed_version = Version::from(version);
+    if parsed_version == Version::Uninitialized {
+        return Err(ParseError::UnsupportedVersion(version.to_string()));
+    }
+    let status_code = code
+        .parse()
+        .ok()
+        .filter(|_| code.len() == 3)
+        .and_then(StatusCode::from_u16)
+        .ok_or_else(bad_line)?;
+    let mut headers = HeaderMap::new();
+    while let Some(remain) = rest {
+        let (line, next) = split_line(remain);
+        rest = next;
+        if line.is_empty() {
+            break;
+        }
+        let (key, val) = process_header_line(line)?;
+        headers.append(key, val);
+    }
+    Ok(ParsedResponse {
+        version: parsed_version,
+        status_code,
+        reason: reason.to_string(),
+        headers,
+        body: Vec::new(),
+    })
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http_request::Method;
+    #[test]
+    fn test_parse_in_one_piece() {
+        let mut parser = RequestParser::new();
+        let req = parser
+            .feed(b"POST /orders HTTP/1.1\r\nContent-Length: 5\r\n\r\nhello")
+            .unwrap()
+            .unwrap();
+        assert_eq!(req.method, Method::POST);
+        assert_eq!(req.path(), "/orders");
+        assert_eq!(req.body, b"hello");
+    }
+    #[test]
+    fn test_head_too_large() {
+        let mut parser = RequestParser::new();
+        parser.feed(b"GET / HTTP/1.1\r\nX-Long: ").unwrap();
+        let chunk = vec![b'a'; 1024];
+        let mut result = Ok(None);
+        for _ in 0..MAX_HEAD_SIZE / chunk.len() + 1 {
+            result = parser.feed(&chunk);
+            if result.is_err() {
+                break;
+            }
+        }
+        let err = result.unwrap_err();
+        assert_eq!(err, ParseError::HeadTooLarge(MAX_HEAD_SIZE));
+        assert_eq!(
+            err.status_code(),
+            StatusCode::REQUEST_HEADER_FIELDS_TOO_LARGE
+        );
+        //结束标记跨在两次feed之间也能找到
+        let mut parser = RequestParser::new();
+        assert!(parser
+            .feed(b"GET / HTTP/1.1\r\nHost: a\r")
+            .unwrap()
+            .is_none());
+        assert!(parser.feed(b"\n\r").unwrap().is_none());
+        let req = parser.feed(b"\n").unwrap().unwrap();
+        assert_eq!(req.header.get("Host"), Some("a"));
+    }
+    #[test]
+    fn test_parse_split_across_reads() {
+        let mut parser = RequestParser::new();
+        assert!(parser
+            .feed(b"POST /orders HTTP/1.1\r\nContent-")
+            .unwrap()
+            .is_none());
+        assert!(parser.feed(b"Length: 11\r\n\r\nhello").unwrap().is_none());
+        let req = parser.feed(b" world").unwrap().unwrap();
+        assert_eq!(req.body, b"hello world");
+    }
+    #[test]
+    fn test_parse_large_body() {
+        let body = "x".repeat(5000);
+        let raw = format!(
+            "POST /orders HTTP/1.1\r\nContent-Length: {}\r\n\r\n{}",
+            body.len(),
+            body
+        );
+        let mut parser = RequestParser::new();
+        let mut result = None;
+        for chunk in raw.as_bytes().chunks(2000) {
+            result = parser.feed(chunk).unwrap();
+        }
+        assert_eq!(result.unwrap().body, body.as_bytes());
+    }
+    #[test]
+    fn test_parse_pipelined() {
+        let mut parser = RequestParser::new();
+        let req = parser
+            .feed(b"POST /a HTTP/1.1\r\nContent-Length: 2\r\n\r\nokGET /b HTTP/1.1\r\n\r\nPOST /c HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n1\r\nx\r\n0\r\n\r\nGET /d")
+            .unwrap()
+            .unwrap();
+        assert_eq!((req.path(), req.body.as_slice()), ("/a", &b"ok"[..]));
+        assert_eq!(parser.feed(&[]).unwrap().unwrap().path(), "/b");
+        assert_eq!(parser.feed(&[]).unwrap().unwrap().body, b"x");
+        assert!(parser.feed(&[]).unwrap().is_none());
+        assert_eq!(parser.buffered(), 6);
+        let req = parser.feed(b" HTTP/1.1\r\n\r\n").unwrap().unwrap();
+        assert_eq!(req.path(), "/d");
+        assert_eq!(parser.buffered(), 0);
+    }
+    #[test]
+    fn test_parse_head_before_body() {
+        let mut parser = RequestParser::new();
+        assert!(parser
+            .feed(b"PUT /upload HTTP/1.1\r\nExpect: 100-continue\r\nContent-Length: 3\r\n\r\n")
+            .unwrap()
+            .is_none());
+        let head = parser.head().unwrap();
+        assert_eq!(head.header.get("Expect"), Some("100-continue"));
+        assert!(head.body.is_empty());
+        assert_eq!(parser.feed(b"abc").unwrap().unwrap().body, b"abc");
+        assert!(parser.head().is_none());
+    }
+    #[test]
+    fn test_parse_max_body_size() {
+        let mut parser = RequestParser::with_max_body_size(4);
+        assert_eq!(
+            parser.feed(b"POST / HTTP/1.1\r\nContent-Length: 5\r\n\r\n"),
+            Err(ParseError::ContentTooLarge(4))
+        );
+        let mut parser = RequestParser::with_max_body_size(4);
+        assert!(parser
+            .feed(b"POST / HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n3\r\nabc\r\n")
+            .unwrap()
+            .is_none());
+        assert_eq!(
+            parser.feed(b"2\r\nde\r\n"),
+            Err(ParseError::ContentTooLarge(4))
+        );
+        let mut parser = RequestParser::with_max_body_size(4);
+        let req = parser
+            .feed(b"POST / HTTP/1.1\r\nContent-Length: 4\r\n\r\nabcd")
+            .unwrap()
+            .unwrap();
+        assert_eq!(req.body, b"abcd");
+    }
+    #[test]
+    fn test_parse_without_body() {
+        let mut parser = RequestParser::new();
+        let req = parser
+            .feed(b"GET / HTTP/1.1\r\nHost: localhost\r\n\r\n")
+            .unwrap()
+            .unwrap();
+        assert_eq!(req.method, Method::GET);
+        assert!(req.body.is_empty());
+    }
+    #[test]
+    fn test_parse_binary_body() {
+        let mut parser = RequestParser::new();
+        let req = parser
+            .feed(b"\r\nPOST /upload HTTP/1.1\r\nContent-Length: 4\r\n\r\n\x89PN\xff")
+            .unwrap()
+            .unwrap();
+        assert_eq!(req.body, b"\x89PN\xff");
+    }
+    #[test]
+    fn test_parse_chunked_body() {
+        let mut parser = RequestParser::new();
+        assert!(parser
+            .feed(b"POST /upload HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n5\r\nhel")
+            .unwrap()
+            .is_none());
+        assert!(parser.feed(b"lo\r\n6\r\n world\r\n").unwrap().is_none());
+        let req = parser
+            .feed(b"0\r\nX-Checksum: 42\r\n\r\n")
+            .unwrap()
+            .unwrap();
+        assert_eq!(req.body, b"hello world");
+        assert_eq!(req.header.get("x-checksum"), Some("42"));
+    }
+    #[test]
+    fn test_parse_transfer_encoding_errors() {
+        let mut parser = RequestParser::new();
+        assert_eq!(
+            parser.feed(b"POST / HTTP/1.1\r\nTransfer-Encoding: gzip\r\n\r\n"),
+            Err(ParseError::UnsupportedTransferEncoding("gzip".into()))
+        );
+        let mut parser = RequestParser::new();
+        assert!(parser
+            .feed(b"POST / HTTP/1.1\r\nTransfer-Encoding: chunked\r\nContent-Length: 3\r\n\r\n")
+            .is_err());
+    }
+    #[test]
+    fn test_parse_response_content_length() {
+        let mut parser = ResponseParser::new();
+        assert!(parser
+            .feed(b"HTTP/1.1 201 Created\r\nContent-Length: 7\r\nX-Id: 5\r\n\r\n{\"a\"")
+            .unwrap()
+            .is_none());
+        let res = parser
+            .feed(b":1}HTTP/1.1 204 No Content\r\n\r\n")
+            .unwrap()
+            .unwrap();
+        assert_eq!(res.version, Version::V1_1);
+        assert_eq!(res.status_code, StatusCode::CREATED);
+        assert_eq!(res.reason, "Created");
+        assert_eq!(res.headers.get("x-id"), Some("5"));
+        let value: serde_json::Value = res.json().unwrap();
+        assert_eq!(value["a"], 1);
+        //多出来的数据是下一个响应
+        let res = parser.feed(b"").unwrap().unwrap();
+        assert_eq!(res.status_code, StatusCode::NO_CONTENT);
+        assert!(parser.finish().unwrap().is_none());
+    }
+    #[test]
+    fn test_parse_response_chunked() {
+        let mut parser = ResponseParser::new();
+        let res = parser
+            .feed(b"HTTP/1.1 100 Continue\r\n\r\nHTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n3\r\nabc\r\n0\r\n\r\n")
+            .unwrap()
+            .unwrap();
+        assert_eq!(res.status_code, StatusCode::OK);
+        assert_eq!(res.text().unwrap(), "abc");
+    }
+    #[test]
+    fn test_parse_response_until_close() {
+        let mut parser = ResponseParser::new();
+        assert!(parser
+            .feed(b"HTTP/1.0 200 OK\r\n\r\npart one, ")
+            .unwrap()
+            .is_none());
+        assert!(parser.feed(b"part two").unwrap().is_none());
+        let res = parser.finish().unwrap().unwrap();
+        assert_eq!(res.body, b"part one, part two");
+        let mut parser = ResponseParser::new();
+        parser
+            .feed(b"HTTP/1.1 200 OK\r\nContent-Length: 10\r\n\r\nshort")
+            .unwrap();
+        assert_eq!(parser.finish(), Err(ParseError::UnexpectedEof));
+    }
+    #[test]
+    fn test_parse_response_head_request() {
+        let mut parser = ResponseParser::for_method(&Method::HEAD);
+        let res = parser
+            .feed(b"HTTP/1.1 200 OK\r\nContent-Length: 120\r\n\r\n")
+            .unwrap()
+            .unwrap();
+        assert!(res.body.is_empty());
+        assert_eq!(res.headers.get("Content-Length"), Some("120"));
+    }
+    #[test]
+    fn test_parse_response_errors() {
+        let mut parser = ResponseParser::new();
+        assert_eq!(
+            parser.feed(b"HTTP/1.1 abc OK\r\n\r\n"),
+            Err(ParseError::BadStatusLine("HTTP/1.1 abc OK".into()))
+        );
+        let mut parser = ResponseParser::new();
+        assert_eq!(
+            parser.feed(b"HTTP/2 200 OK\r\n\r\n"),
+            Err(ParseError::UnsupportedVersion("HTTP/2".into()))
+        );
+        let res: HttpResponse = ResponseParser::new()
+            .feed(b"HTTP/1.1 404 Not Found\r\nContent-Length: 2\r\n\r\nno")
+            .unwrap()
+            .unwrap()
+            .into();
+        assert_eq!(res.status_code(), StatusCode::NOT_FOUND);
+        assert_eq!(res.body(), b"no");
+    }
+    #[test]
+    fn test_parse_errors() {
+        let mut parser = RequestParser::new();
+        assert_eq!(
+            parser.feed(b"POST / HTTP/1.1\r\nContent-Length: ten\r\n\r\n"),
+            Err(ParseError::InvalidContentLength("ten".into()))
+        );
+        let mut parser = RequestParser::new();
+        assert_eq!(
+            parser.feed(b"POST / HTTP/1.1\r\nContent-Length: +2\r\n\r\nab"),
+            Err(ParseError::InvalidContentLength("+2".into()))
+        );
+        let mut parser = RequestParser::new();
+        assert_eq!(
+            parser.feed(b"GARBAGE\r\n\r\n"),
+            Err(ParseError::BadRequestLine("GARBAGE".into()))
+        );
+    }
+}
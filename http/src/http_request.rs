@@ -1,89 +1,407 @@
-use std::collections::HashMap;
+use std::{
+    error::Error,
+    fmt,
+    io::{self, BufWriter, Write},
+    str::Utf8Error,
+};
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+use serde::de::DeserializeOwned;
+
+use crate::{
+    http_header::HeaderMap,
+    http_parser::RequestParser,
+    http_response::StatusCode,
+    http_uri::{is_authority, percent_decode, Query, Uri},
+};
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum Method {
     GET,
+    HEAD,
     POST,
+    PUT,
+    DELETE,
+    CONNECT,
+    OPTIONS,
+    TRACE,
+    PATCH,
+    Extension(String), //自定义的扩展方法
     Uninitialized,
 }
+impl Method {
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::GET => "GET",
+            Self::HEAD => "HEAD",
+            Self::POST => "POST",
+            Self::PUT => "PUT",
+            Self::DELETE => "DELETE",
+            Self::CONNECT => "CONNECT",
+            Self::OPTIONS => "OPTIONS",
+            Self::TRACE => "TRACE",
+            Self::PATCH => "PATCH",
+            Self::Extension(method) => method,
+            Self::Uninitialized => "",
+        }
+    }
+}
 impl From<&str> for Method {
     fn from(value: &str) -> Self {
         match value {
             "GET" => Self::GET,
+            "HEAD" => Self::HEAD,
             "POST" => Self::POST,
+            "PUT" => Self::PUT,
+            "DELETE" => Self::DELETE,
+            "CONNECT" => Self::CONNECT,
+            "OPTIONS" => Self::OPTIONS,
+            "TRACE" => Self::TRACE,
+            "PATCH" => Self::PATCH,
+            _ if is_token(value) => Self::Extension(value.to_string()),
             _ => Self::Uninitialized,
         }
     }
 }
-#[derive(Debug, PartialEq)]
+impl fmt::Display for Method {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+//方法名必须是RFC 9110 中的token
+fn is_token(s: &str) -> bool {
+    !s.is_empty()
+        && s.bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b"!#$%&'*+-.^_`|~".contains(&b))
+}
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub enum Version {
+    V1_0,
     V1_1,
-    V2_2,
-    V3_3,
     Uninitialized,
 }
+impl Version {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::V1_0 => "HTTP/1.0",
+            Self::V1_1 => "HTTP/1.1",
+            Self::Uninitialized => "",
+        }
+    }
+}
 impl From<&str> for Version {
+    //1.x中比1.1更高的次版本号按1.1处理(RFC 9110 2.5)
     fn from(value: &str) -> Self {
-        match value {
-            "HTTP/1.1" => Self::V1_1,
+        match value.strip_prefix("HTTP/1.") {
+            Some("0") => Self::V1_0,
+            Some(minor) if minor.len() == 1 && minor.as_bytes()[0].is_ascii_digit() => Self::V1_1,
             _ => Self::Uninitialized,
         }
     }
 }
-#[derive(Debug, PartialEq)]
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+#[derive(Debug, Clone, PartialEq)]
 pub enum Resource {
-    Path(String),
+    //origin形式,path已经过百分号解码,raw_path保留原始编码用于转发
+    Path {
+        path: String,
+        raw_path: String,
+        query: Query,
+    },
+    Absolute(Uri),     //absolute形式,代理请求使用
+    Authority(String), //authority形式,只用于CONNECT
+    Asterisk,          //asterisk形式,只用于OPTIONS
+}
+impl Resource {
+    //按RFC 9112 的四种形式解析请求目标
+    fn parse(method: &Method, target: &str) -> Option<Self> {
+        if *method == Method::CONNECT {
+            return is_authority(target).then(|| Self::Authority(target.to_string()));
+        }
+        if target == "*" {
+            return (*method == Method::OPTIONS).then_some(Self::Asterisk);
+        }
+        if target.starts_with('/') {
+            let (path, query) = target.split_once('?').unwrap_or((target, ""));
+            return Some(Self::Path {
+                path: percent_decode(path),
+                raw_path: path.to_string(),
+                query: Query::parse(query),
+            });
+        }
+        Uri::parse(target).map(Self::Absolute)
+    }
+    //absolute形式转成发给源服务器的origin形式,保留原始编码
+    pub fn origin_form(uri: &Uri) -> Self {
+        Self::Path {
+            path: uri.path().to_string(),
+            raw_path: uri.raw_path().to_string(),
+            query: uri.query().clone(),
+        }
+    }
+    //用于路由匹配的路径,authority和asterisk形式返回原始目标
+    pub fn path(&self) -> &str {
+        match self {
+            Self::Path { path, .. } => path,
+            Self::Absolute(uri) => uri.path(),
+            Self::Authority(authority) => authority,
+            Self::Asterisk => "*",
+        }
+    }
+    pub fn query(&self) -> Option<&Query> {
+        match self {
+            Self::Path { query, .. } => Some(query),
+            Self::Absolute(uri) => Some(uri.query()),
+            _ => None,
+        }
+    }
+    //写在请求行里的请求目标,origin形式使用原始编码的路径
+    pub fn target(&self) -> String {
+        match self {
+            Self::Path {
+                raw_path, query, ..
+            } if query.is_empty() => raw_path.clone(),
+            Self::Path {
+                raw_path, query, ..
+            } => format!("{}?{}", raw_path, query.as_str()),
+            Self::Absolute(uri) => uri.to_string(),
+            Self::Authority(authority) => authority.clone(),
+            Self::Asterisk => "*".to_string(),
+        }
+    }
 }
-#[derive(Debug)]
+#[derive(Debug, PartialEq)]
 pub struct HttpRequest {
     pub method: Method,
     pub version: Version,
     pub resource: Resource,
-    pub header: HashMap<String, String>,
-    pub body: String,
-}
-fn process_req_line(s: &str) -> (Method, Resource, Version) {
-    let mut words = s.split_whitespace();
-    let method = words.next().unwrap();
-    let resource = words.next().unwrap();
-    let version: &str = words.next().unwrap();
-    (
-        Method::from(method),
-        Resource::Path(resource.to_string()),
-        Version::from(version),
-    )
-}
-fn process_header_line(s: &str) -> (String, String) {
-    let mut header_items = s.split(":");
-    (
-        header_items.next().unwrap().to_string(),
-        header_items.next().unwrap().to_string(),
-    )
-}
-impl From<String> for HttpRequest {
-    fn from(value: String) -> Self {
-        let mut parsed_method = Method::Uninitialized;
-        let mut parsed_version = Version::V1_1;
-        let mut parsed_resource = Resource::Path("".to_string());
-        let mut parsed_header = HashMap::new();
-        let mut parsed_msg_body = "";
-        for line in value.lines() {
-            if line.contains("HTTP") {
-                (parsed_method, parsed_resource, parsed_version) = process_req_line(line);
-            } else if line.contains(":") {
-                let (key, val) = process_header_line(line);
-                parsed_header.insert(key, val);
-            } else if line.len() != 0 {
-                parsed_msg_body = line;
+    pub header: HeaderMap,
+    pub body: Vec<u8>,
+    pub params: Vec<(String, String)>, //路由匹配出的路径参数,由路由器填写
+}
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseError {
+    BadRequestLine(String),
+    BadHeader(String),
+    UnsupportedVersion(String),
+    InvalidUtf8,
+    InvalidContentLength(String),
+    InvalidChunk(String),
+    UnsupportedTransferEncoding(String),
+    BadStatusLine(String),
+    UnexpectedEof,
+    ContentTooLarge(usize),
+    HeadTooLarge(usize),
+}
+impl ParseError {
+    //错误对应的响应状态码
+    pub fn status_code(&self) -> StatusCode {
+        match self {
+            Self::UnsupportedVersion(_) => StatusCode::HTTP_VERSION_NOT_SUPPORTED,
+            Self::UnsupportedTransferEncoding(_) => StatusCode::NOT_IMPLEMENTED,
+            Self::ContentTooLarge(_) => StatusCode::CONTENT_TOO_LARGE,
+            Self::HeadTooLarge(_) => StatusCode::REQUEST_HEADER_FIELDS_TOO_LARGE,
+            _ => StatusCode::BAD_REQUEST,
+        }
+    }
+}
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::BadRequestLine(line) => write!(f, "bad request line: {:?}", line),
+            Self::BadHeader(line) => write!(f, "bad header line: {:?}", line),
+            Self::UnsupportedVersion(version) => write!(f, "unsupported version: {}", version),
+            Self::InvalidUtf8 => write!(f, "message head is not valid utf-8"),
+            Self::InvalidContentLength(value) => write!(f, "invalid content-length: {:?}", value),
+            Self::InvalidChunk(line) => write!(f, "invalid chunk: {:?}", line),
+            Self::UnsupportedTransferEncoding(value) => {
+                write!(f, "unsupported transfer-encoding: {:?}", value)
+            }
+            Self::BadStatusLine(line) => write!(f, "bad status line: {:?}", line),
+            Self::UnexpectedEof => write!(f, "connection closed before message completed"),
+            Self::ContentTooLarge(max) => write!(f, "body larger than {} bytes", max),
+            Self::HeadTooLarge(max) => write!(f, "message head larger than {} bytes", max),
+        }
+    }
+}
+impl Error for ParseError {}
+fn process_req_line(s: &str) -> Result<(Method, Resource, Version), ParseError> {
+    let bad_line = || ParseError::BadRequestLine(s.to_string());
+    let words: Vec<_> = s.split_whitespace().collect();
+    let [method, resource, version] = words[..] else {
+        return Err(bad_line());
+    };
+    let (major, minor) = version
+        .strip_prefix("HTTP/")
+        .and_then(|v| v.split_once('.'))
+        .ok_or_else(bad_line)?;
+    let is_digit = |part: &str| part.len() == 1 && part.bytes().all(|b| b.is_ascii_digit());
+    if !is_digit(major) || !is_digit(minor) {
+        return Err(bad_line());
+    }
+    if major != "1" {
+        return Err(ParseError::UnsupportedVersion(version.to_string()));
+    }
+    let method = Method::from(method);
+    if method == Method::Uninitialized {
+        return Err(bad_line());
+    }
+    let resource = Resource::parse(&method, resource).ok_or_else(bad_line)?;
+    Ok((method, resource, Version::from(version)))
+}
+//只按第一个冒号切分,值两端的空白去掉
+pub(crate) fn process_header_line(s: &str) -> Result<(String, String), ParseError> {
+    match s.split_once(':') {
+        Some((key, val)) if is_token(key) => Ok((key.to_string(), val.trim().to_string())),
+        _ => Err(ParseError::BadHeader(s.to_string())),
+    }
+}
+//切出一行(去掉行尾的\r\n),返回该行和剩余部分
+pub(crate) fn split_line(s: &str) -> (&str, Option<&str>) {
+    match s.split_once('\n') {
+        Some((line, rest)) => (line.strip_suffix('\r').unwrap_or(line), Some(rest)),
+        None => (s.strip_suffix('\r').unwrap_or(s), None),
+    }
+}
+impl HttpRequest {
+    //解码后的请求路径,不含查询字符串
+    pub fn path(&self) -> &str {
+        self.resource.path()
+    }
+    pub fn query(&self, name: &str) -> Option<&str> {
+        self.resource.query().and_then(|query| query.get(name))
+    }
+    pub fn query_pairs(&self) -> &[(String, String)] {
+        self.resource.query().map_or(&[], |query| query.pairs())
+    }
+    //取路径参数,如路由/orders/:id中的id
+    pub fn param(&self, name: &str) -> Option<&str> {
+        self.params
+            .iter()
+            .find(|(k, _)| k == name)
+            .map(|(_, v)| v.as_str())
+    }
+    pub fn parse(s: &str) -> Result<Self, ParseError> {
+        Self::parse_bytes(s.as_bytes())
+    }
+    //解析缓冲区开头的一个完整请求,按Content-Length或chunked确定请求体的长度
+    //返回请求和消耗掉的字节数,数据还不完整时返回Ok(None)
+    pub fn parse_message(buf: &[u8]) -> Result<Option<(Self, usize)>, ParseError> {
+        let mut parser = RequestParser::new();
+        Ok(parser
+            .feed(buf)?
+            .map(|req| (req, buf.len() - parser.buffered())))
+    }
+    //请求头必须是utf-8,空行之后的数据全部作为请求体
+    pub fn parse_bytes(buf: &[u8]) -> Result<Self, ParseError> {
+        let buf = trim_leading_newlines(buf);
+        let head_end = find_head_end(buf).unwrap_or(buf.len());
+        let head = std::str::from_utf8(&buf[..head_end]).map_err(|_| ParseError::InvalidUtf8)?;
+        let (line, mut rest) = split_line(head);
+        let (method, resource, version) = process_req_line(line)?;
+        let mut header = HeaderMap::new();
+        while let Some(remain) = rest {
+            let (line, next) = split_line(remain);
+            rest = next;
+            if line.is_empty() {
+                break;
+            }
+            let (key, val) = process_header_line(line)?;
+            header.append(key, val);
+        }
+        Ok(Self {
+            method,
+            version,
+            resource,
+            header,
+            body: buf[head_end..].to_vec(),
+            params: Vec::new(),
+        })
+    }
+    //请求体按utf-8文本读取
+    pub fn text(&self) -> Result<&str, Utf8Error> {
+        std::str::from_utf8(&self.body)
+    }
+    //请求体按json反序列化
+    pub fn json<T: DeserializeOwned>(&self) -> serde_json::Result<T> {
+        serde_json::from_slice(&self.body)
+    }
+    //是否保持连接: 1.1默认保持,1.0只有明确要求keep-alive时才保持
+    pub fn is_keep_alive(&self) -> bool {
+        match self.version {
+            Version::V1_1 => !self.header.has_token("Connection", "close"),
+            Version::V1_0 => self.header.has_token("Connection", "keep-alive"),
+            Version::Uninitialized => false,
+        }
+    }
+    //客户端发送请求: 请求体总是用Content-Length定界
+    pub fn write_to<T: Write>(&self, w: &mut T) -> io::Result<()> {
+        let mut w = BufWriter::new(w);
+        let version = match self.version {
+            Version::Uninitialized => Version::V1_1,
+            version => version,
+        };
+        write!(
+            w,
+            "{} {} {}\r\n",
+            self.method,
+            self.resource.target(),
+            version
+        )?;
+        for (key, val) in self.header.iter() {
+            if key.eq_ignore_ascii_case("Content-Length")
+                || key.eq_ignore_ascii_case("Transfer-Encoding")
+            {
+                continue;
             }
+            write!(w, "{}: {}\r\n", key, val)?;
         }
-        Self {
-            method: parsed_method,
-            version: parsed_version,
-            resource: parsed_resource,
-            header: parsed_header,
-            body: parsed_msg_body.to_string(),
+        //POST/PUT/PATCH即使没有请求体也要告诉服务端长度为0
+        let expects_body = matches!(self.method, Method::POST | Method::PUT | Method::PATCH);
+        if !self.body.is_empty() || expects_body {
+            write!(w, "Content-Length: {}\r\n", self.body.len())?;
         }
+        w.write_all(b"\r\n")?;
+        w.write_all(&self.body)?;
+        w.flush()
+    }
+}
+//请求行之前的空行可以忽略
+pub(crate) fn trim_leading_newlines(buf: &[u8]) -> &[u8] {
+    let start = buf
+        .iter()
+        .position(|b| *b != b'\r' && *b != b'\n')
+        .unwrap_or(buf.len());
+    &buf[start..]
+}
+//找到请求头的结束位置(空行之后)
+pub(crate) fn find_head_end(buf: &[u8]) -> Option<usize> {
+    let crlf = buf.windows(4).position(|w| w == b"\r\n\r\n").map(|i| i + 4);
+    let lf = buf.windows(2).position(|w| w == b"\n\n").map(|i| i + 2);
+    match (crlf, lf) {
+        (Some(a), Some(b)) => Some(a.min(b)),
+        (a, b) => a.or(b),
+    }
+}
+impl TryFrom<&str> for HttpRequest {
+    type Error = ParseError;
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        Self::parse(value)
+    }
+}
+impl TryFrom<String> for HttpRequest {
+    type Error = ParseError;
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        Self::parse(&value)
+    }
+}
+impl TryFrom<&[u8]> for HttpRequest {
+    type Error = ParseError;
+    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+        Self::parse_bytes(value)
     }
 }
 #[cfg(test)]
@@ -95,21 +413,174 @@ mod test {
         assert_eq!(m, Method::GET);
     }
     #[test]
+    fn test_method_extension() {
+        assert_eq!(Method::from("PATCH"), Method::PATCH);
+        assert_eq!(
+            Method::from("PROPFIND"),
+            Method::Extension("PROPFIND".into())
+        );
+        assert_eq!(Method::from("PROPFIND").as_str(), "PROPFIND");
+        assert_eq!(Method::from("GE(T"), Method::Uninitialized);
+        assert_eq!(
+            HttpRequest::parse("G@T / HTTP/1.1\r\n\r\n").unwrap_err(),
+            ParseError::BadRequestLine("G@T / HTTP/1.1".into())
+        );
+    }
+    #[test]
     fn test_version_into() {
         let v: Version = "HTTP/1.1".into();
         assert_eq!(v, Version::V1_1);
+        assert_eq!(Version::from("HTTP/1.0"), Version::V1_0);
+        assert_eq!(Version::from("HTTP/1.2"), Version::V1_1);
+        assert_eq!(Version::from("HTTP/2.0"), Version::Uninitialized);
+        assert_eq!(Version::V1_0.to_string(), "HTTP/1.0");
     }
     #[test]
-    fn test_http_request_into() {
+    fn test_http_request_keep_alive() {
+        let keep_alive = |raw: &str| HttpRequest::parse(raw).unwrap().is_keep_alive();
+        assert!(keep_alive("GET / HTTP/1.1\r\n\r\n"));
+        assert!(!keep_alive("GET / HTTP/1.1\r\nConnection: close\r\n\r\n"));
+        assert!(!keep_alive("GET / HTTP/1.0\r\n\r\n"));
+        assert!(keep_alive(
+            "GET / HTTP/1.0\r\nConnection: Keep-Alive\r\n\r\n"
+        ));
+        assert_eq!(
+            HttpRequest::parse("GET / HTTP/1.0\r\n\r\n")
+                .unwrap()
+                .version,
+            Version::V1_0
+        );
+    }
+    #[test]
+    fn test_http_request_parse() {
         let s: String = String::from("GET /greeting HTTP/1.1\r\nHost: localhost:3000\r\nUser-Agent:curl/7.71\r\nAccept:*/*\r\n\r\n");
-        let mut header_expected: HashMap<String, String> = HashMap::new();
-        header_expected.insert("Host".into(), " localhost".into());
-        header_expected.insert("Accept".into(), "*/*".into());
-        header_expected.insert("User-Agent".into(), "curl/7.71".into());
-        let req: HttpRequest = s.into();
+        let mut header_expected = HeaderMap::new();
+        header_expected.append("Host", "localhost:3000");
+        header_expected.append("User-Agent", "curl/7.71");
+        header_expected.append("Accept", "*/*");
+        let req = HttpRequest::try_from(s).unwrap();
         assert_eq!(Method::GET, req.method);
         assert_eq!(Version::V1_1, req.version);
-        assert_eq!(Resource::Path("/greeting".to_string()), req.resource);
+        assert_eq!(
+            Resource::Path {
+                path: "/greeting".to_string(),
+                raw_path: "/greeting".to_string(),
+                query: Query::default()
+            },
+            req.resource
+        );
         assert_eq!(header_expected, req.header);
     }
+    #[test]
+    fn test_http_request_parse_repeated_header() {
+        let req = HttpRequest::parse(
+            "GET / HTTP/1.1\r\naccept: text/html\r\nAccept:  application/json \r\n\r\n",
+        )
+        .unwrap();
+        assert_eq!(req.header.get("ACCEPT"), Some("text/html"));
+        assert_eq!(
+            req.header.get_all("Accept").collect::<Vec<_>>(),
+            ["text/html", "application/json"]
+        );
+    }
+    #[test]
+    fn test_http_request_parse_query() {
+        let req = HttpRequest::parse("GET /my%20orders?id=5&q=a+b HTTP/1.1\r\n\r\n").unwrap();
+        assert_eq!(req.path(), "/my orders");
+        assert_eq!(req.query("id"), Some("5"));
+        assert_eq!(req.query("q"), Some("a b"));
+        assert_eq!(req.query("missing"), None);
+        assert_eq!(
+            req.query_pairs(),
+            [("id".into(), "5".into()), ("q".into(), "a b".into())]
+        );
+    }
+    #[test]
+    fn test_http_request_target_forms() {
+        let req =
+            HttpRequest::parse("GET http://example.com:8080/orders?id=5 HTTP/1.1\r\n\r\n").unwrap();
+        assert!(
+            matches!(&req.resource, Resource::Absolute(uri) if uri.authority() == "example.com:8080")
+        );
+        assert_eq!(req.path(), "/orders");
+        assert_eq!(req.query("id"), Some("5"));
+        let req = HttpRequest::parse("CONNECT example.com:443 HTTP/1.1\r\n\r\n").unwrap();
+        assert_eq!(req.resource, Resource::Authority("example.com:443".into()));
+        let req = HttpRequest::parse("OPTIONS * HTTP/1.1\r\n\r\n").unwrap();
+        assert_eq!(req.resource, Resource::Asterisk);
+        assert!(req.query_pairs().is_empty());
+        for line in [
+            "GET * HTTP/1.1",
+            "CONNECT /path HTTP/1.1",
+            "GET example.com HTTP/1.1",
+        ] {
+            assert_eq!(
+                HttpRequest::parse(&format!("{}\r\n\r\n", line)).unwrap_err(),
+                ParseError::BadRequestLine(line.into())
+            );
+        }
+    }
+    #[test]
+    fn test_http_request_parse_body() {
+        let req = HttpRequest::parse("POST /orders HTTP/1.1\r\nAccept:*/*\r\n\r\n{\"id\":1}\r\n")
+            .unwrap();
+        assert_eq!(Method::POST, req.method);
+        assert_eq!(b"{\"id\":1}\r\n", &req.body[..]);
+        assert_eq!(req.text().unwrap(), "{\"id\":1}\r\n");
+        let value: serde_json::Value = req.json().unwrap();
+        assert_eq!(value["id"], 1);
+    }
+    #[test]
+    fn test_http_request_parse_message() {
+        let raw = b"POST /a HTTP/1.1\r\nContent-Length: 3\r\n\r\nabcGET /b HTTP/1.1\r\n\r\n";
+        let (req, used) = HttpRequest::parse_message(raw).unwrap().unwrap();
+        assert_eq!(req.body, b"abc");
+        let (req, rest) = HttpRequest::parse_message(&raw[used..]).unwrap().unwrap();
+        assert_eq!(req.path(), "/b");
+        assert_eq!(used + rest, raw.len());
+        assert_eq!(HttpRequest::parse_message(&raw[..20]), Ok(None));
+    }
+    #[test]
+    fn test_http_request_write_to() {
+        let mut req = HttpRequest::parse(
+            "POST /a%20b/c?x=1&y=%2F HTTP/1.1\r\nHost: localhost\r\nContent-Length: 99\r\n\r\n",
+        )
+        .unwrap();
+        req.body = b"{}".to_vec();
+        let mut raw = Vec::new();
+        req.write_to(&mut raw).unwrap();
+        assert_eq!(
+            String::from_utf8(raw.clone()).unwrap(),
+            "POST /a%20b/c?x=1&y=%2F HTTP/1.1\r\nHost: localhost\r\nContent-Length: 2\r\n\r\n{}"
+        );
+        let parsed = HttpRequest::parse_bytes(&raw).unwrap();
+        assert_eq!(parsed.resource, req.resource);
+        assert_eq!(parsed.body, req.body);
+    }
+    #[test]
+    fn test_http_request_parse_errors() {
+        assert_eq!(
+            HttpRequest::parse("GET /greeting\r\n\r\n").unwrap_err(),
+            ParseError::BadRequestLine("GET /greeting".into())
+        );
+        assert_eq!(
+            HttpRequest::parse("GET / HTTP/1.1\r\nno colon here\r\n\r\n").unwrap_err(),
+            ParseError::BadHeader("no colon here".into())
+        );
+        assert_eq!(
+            HttpRequest::parse("GET / HTTP/2.0\r\n\r\n").unwrap_err(),
+            ParseError::UnsupportedVersion("HTTP/2.0".into())
+        );
+        assert_eq!(
+            HttpRequest::parse("GET / FTP/1.1\r\n\r\n").unwrap_err(),
+            ParseError::BadRequestLine("GET / FTP/1.1".into())
+        );
+        assert_eq!(
+            HttpRequest::try_from(&b"GET / HTTP/1.1\r\nX-Bad: \xff\r\n\r\n"[..]).unwrap_err(),
+            ParseError::InvalidUtf8
+        );
+        let req = HttpRequest::try_from(&b"POST / HTTP/1.1\r\n\r\n\xff\x00\xfe"[..]).unwrap();
+        assert_eq!(req.body, [0xff, 0x00, 0xfe]);
+        assert!(req.text().is_err());
+    }
 }
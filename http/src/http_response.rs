@@ -1,107 +1,504 @@
-use std::{collections::HashMap, io::Write};
-#[derive(Debug, PartialEq, Clone)]
-pub struct HttpResponse<'a> {
-    version: &'a str,
-    status_code: &'a str,
-    status_text: &'a str,
-    headers: Option<HashMap<&'a str, &'a str>>,
-    body: Option<String>,
-}
-impl<'a> Default for HttpResponse<'a> {
+use std::{
+    fmt,
+    io::{self, BufWriter, Read, Write},
+    str::Utf8Error,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use serde::Serialize;
+
+use crate::{
+    http_chunked::ChunkedEncoder,
+    http_header::HeaderMap,
+    http_request::{Method, Version},
+};
+
+const SERVER_NAME: &str = concat!("rust_simple_web/", env!("CARGO_PKG_VERSION"));
+
+//IANA登记的状态码及其原因短语
+macro_rules! status_codes {
+    ($(($code:expr, $name:ident, $phrase:expr);)+) => {
+        impl StatusCode {
+            $(pub const $name: StatusCode = StatusCode($code);)+
+            //未登记的状态码没有原因短语
+            pub fn reason_phrase(&self) -> Option<&'static str> {
+                match self.0 {
+                    $($code => Some($phrase),)+
+                    _ => None,
+                }
+            }
+        }
+    };
+}
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct StatusCode(u16);
+status_codes! {
+    (100, CONTINUE, "Continue");
+    (101, SWITCHING_PROTOCOLS, "Switching Protocols");
+    (102, PROCESSING, "Processing");
+    (103, EARLY_HINTS, "Early Hints");
+    (200, OK, "OK");
+    (201, CREATED, "Created");
+    (202, ACCEPTED, "Accepted");
+    (203, NON_AUTHORITATIVE_INFORMATION, "Non-Authoritative Information");
+    (204, NO_CONTENT, "No Content");
+    (205, RESET_CONTENT, "Reset Content");
+    (206, PARTIAL_CONTENT, "Partial Content");
+    (207, MULTI_STATUS, "Multi-Status");
+    (208, ALREADY_REPORTED, "Already Reported");
+    (226, IM_USED, "IM Used");
+    (300, MULTIPLE_CHOICES, "Multiple Choices");
+    (301, MOVED_PERMANENTLY, "Moved Permanently");
+    (302, FOUND, "Found");
+    (303, SEE_OTHER, "See Other");
+    (304, NOT_MODIFIED, "Not Modified");
+    (305, USE_PROXY, "Use Proxy");
+    (307, TEMPORARY_REDIRECT, "Temporary Redirect");
+    (308, PERMANENT_REDIRECT, "Permanent Redirect");
+    (400, BAD_REQUEST, "Bad Request");
+    (401, UNAUTHORIZED, "Unauthorized");
+    (402, PAYMENT_REQUIRED, "Payment Required");
+    (403, FORBIDDEN, "Forbidden");
+    (404, NOT_FOUND, "Not Found");
+    (405, METHOD_NOT_ALLOWED, "Method Not Allowed");
+    (406, NOT_ACCEPTABLE, "Not Acceptable");
+    (407, PROXY_AUTHENTICATION_REQUIRED, "Proxy Authentication Required");
+    (408, REQUEST_TIMEOUT, "Request Timeout");
+    (409, CONFLICT, "Conflict");
+    (410, GONE, "Gone");
+    (411, LENGTH_REQUIRED, "Length Required");
+    (412, PRECONDITION_FAILED, "Precondition Failed");
+    (413, CONTENT_TOO_LARGE, "Content Too Large");
+    (414, URI_TOO_LONG, "URI Too Long");
+    (415, UNSUPPORTED_MEDIA_TYPE, "Unsupported Media Type");
+    (416, RANGE_NOT_SATISFIABLE, "Range Not Satisfiable");
+    (417, EXPECTATION_FAILED, "Expectation Failed");
+    (421, MISDIRECTED_REQUEST, "Misdirected Request");
+    (422, UNPROCESSABLE_CONTENT, "Unprocessable Content");
+    (423, LOCKED, "Locked");
+    (424, FAILED_DEPENDENCY, "Failed Dependency");
+    (425, TOO_EARLY, "Too Early");
+    (426, UPGRADE_REQUIRED, "Upgrade Required");
+    (428, PRECONDITION_REQUIRED, "Precondition Required");
+    (429, TOO_MANY_REQUESTS, "Too Many Requests");
+    (431, REQUEST_HEADER_FIELDS_TOO_LARGE, "Request Header Fields Too Large");
+    (451, UNAVAILABLE_FOR_LEGAL_REASONS, "Unavailable For Legal Reasons");
+    (500, INTERNAL_SERVER_ERROR, "Internal Server Error");
+    (501, NOT_IMPLEMENTED, "Not Implemented");
+    (502, BAD_GATEWAY, "Bad Gateway");
+    (503, SERVICE_UNAVAILABLE, "Service Unavailable");
+    (504, GATEWAY_TIMEOUT, "Gateway Timeout");
+    (505, HTTP_VERSION_NOT_SUPPORTED, "HTTP Version Not Supported");
+    (506, VARIANT_ALSO_NEGOTIATES, "Variant Also Negotiates");
+    (507, INSUFFICIENT_STORAGE, "Insufficient Storage");
+    (508, LOOP_DETECTED, "Loop Detected");
+    (510, NOT_EXTENDED, "Not Extended");
+    (511, NETWORK_AUTHENTICATION_REQUIRED, "Network Authentication Required");
+}
+impl StatusCode {
+    //状态码必须是三位数
+    pub fn from_u16(code: u16) -> Option<Self> {
+        (100..1000).contains(&code).then_some(Self(code))
+    }
+    pub fn as_u16(&self) -> u16 {
+        self.0
+    }
+    pub fn is_informational(&self) -> bool {
+        (100..200).contains(&self.0)
+    }
+    pub fn is_success(&self) -> bool {
+        (200..300).contains(&self.0)
+    }
+    pub fn is_redirection(&self) -> bool {
+        (300..400).contains(&self.0)
+    }
+    pub fn is_client_error(&self) -> bool {
+        (400..500).contains(&self.0)
+    }
+    pub fn is_server_error(&self) -> bool {
+        (500..600).contains(&self.0)
+    }
+}
+impl Default for StatusCode {
+    fn default() -> Self {
+        Self::OK
+    }
+}
+impl fmt::Display for StatusCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+impl TryFrom<u16> for StatusCode {
+    type Error = u16;
+    fn try_from(value: u16) -> Result<Self, Self::Error> {
+        Self::from_u16(value).ok_or(value)
+    }
+}
+//流式响应体,按块产生数据,用chunked编码边产生边发送
+pub struct BodyStream(Box<dyn Iterator<Item = io::Result<Vec<u8>>> + Send>);
+impl BodyStream {
+    pub fn from_chunks<I>(chunks: I) -> Self
+    where
+        I: IntoIterator,
+        I::IntoIter: Send + 'static,
+        I::Item: Into<Vec<u8>>,
+    {
+        Self(Box::new(chunks.into_iter().map(|chunk| Ok(chunk.into()))))
+    }
+    //每次从reader中读出一块数据
+    pub fn from_reader<R: Read + Send + 'static>(mut reader: R) -> Self {
+        let mut buf = vec![0; 8192];
+        Self(Box::new(std::iter::from_fn(move || loop {
+            match reader.read(&mut buf) {
+                Ok(0) => return None,
+                Ok(n) => return Some(Ok(buf[..n].to_vec())),
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                Err(err) => return Some(Err(err)),
+            }
+        })))
+    }
+}
+impl Iterator for BodyStream {
+    type Item = io::Result<Vec<u8>>;
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next()
+    }
+}
+impl fmt::Debug for BodyStream {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("BodyStream(..)")
+    }
+}
+#[derive(Debug)]
+pub enum ResponseBody {
+    Full(Vec<u8>),
+    Stream(BodyStream),
+}
+//流式响应体无法比较内容,总是不相等
+impl PartialEq for ResponseBody {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Full(a), Self::Full(b)) => a == b,
+            _ => false,
+        }
+    }
+}
+impl From<Vec<u8>> for ResponseBody {
+    fn from(value: Vec<u8>) -> Self {
+        Self::Full(value)
+    }
+}
+impl From<String> for ResponseBody {
+    fn from(value: String) -> Self {
+        Self::Full(value.into_bytes())
+    }
+}
+impl From<&str> for ResponseBody {
+    fn from(value: &str) -> Self {
+        Self::Full(value.as_bytes().to_vec())
+    }
+}
+impl From<BodyStream> for ResponseBody {
+    fn from(value: BodyStream) -> Self {
+        Self::Stream(value)
+    }
+}
+#[derive(Debug, PartialEq)]
+pub struct HttpResponse {
+    version: Version,
+    status_code: StatusCode,
+    headers: HeaderMap,
+    body: Option<ResponseBody>,
+}
+impl Default for HttpResponse {
     fn default() -> Self {
         Self {
-            version: "HTTP/1.1".into(),
-            status_code: "200".into(),
-            status_text: "OK".into(),
-            headers: None,
+            version: Version::V1_1,
+            status_code: StatusCode::OK,
+            headers: HeaderMap::new(),
             body: None,
         }
     }
 }
-impl<'a> From<HttpResponse<'a>> for String {
-    fn from(value: HttpResponse<'a>) -> Self {
-        let res = value.clone();
-        format!(
-            "{} {} {}\r\n{} Content-Length: {}\r\n\r\n{}",
-            res.version(),
-            res.status_code(),
-            res.status_text(),
-            res.header(),
-            res.body().len(),
-            res.body()
-        )
-    }
-}
-impl<'a> HttpResponse<'a> {
-    pub fn new(
-        status_code: &'a str,
-        headers: Option<HashMap<&'a str, &'a str>>,
-        body: Option<String>,
-    ) -> Self {
-        let mut response = Self::default();
-        if status_code != "200" {
-            response.status_code = status_code.into();
-        }
-        response.headers = match &headers {
-            Some(_h) => headers,
+impl From<HttpResponse> for Vec<u8> {
+    fn from(value: HttpResponse) -> Self {
+        let mut bytes = Vec::new();
+        //写入Vec只有流式响应体本身出错时才会失败
+        let _ = value.send_response(&mut bytes);
+        bytes
+    }
+}
+impl HttpResponse {
+    pub fn new(status_code: StatusCode, headers: Option<HeaderMap>, body: Option<Vec<u8>>) -> Self {
+        let headers = match headers {
+            Some(h) => h,
             _ => {
-                let mut h = HashMap::new();
+                let mut h = HeaderMap::new();
                 h.insert("Content-Type", "text/html");
-                Some(h)
+                h
             }
         };
-        response.status_text = match response.status_code {
-            "200" => "OK",
-            "404" => "Not Found".into(),
-            "505" => "Internal Server Error".into(),
-            "400" | _ => "Bad Request".into(),
-        };
-        response.body = body;
+        Self {
+            status_code,
+            headers,
+            body: body.map(ResponseBody::Full),
+            ..Self::default()
+        }
+    }
+    //流式响应,响应体用chunked编码分块发送
+    pub fn new_stream(
+        status_code: StatusCode,
+        headers: Option<HeaderMap>,
+        body: BodyStream,
+    ) -> Self {
+        let mut response = Self::new(status_code, headers, None);
+        response.body = Some(ResponseBody::Stream(body));
         response
     }
-    pub fn send_response<T: Write>(&self, write_stream: &mut T) -> Result<(), std::io::Error> {
-        let res = self.clone();
-        let response_str: String = String::from(res);
-        write!(write_stream, "{}", response_str)
+    pub fn builder() -> ResponseBuilder {
+        ResponseBuilder::new()
     }
-    pub fn version(&self) -> &str {
-        &self.version
+    //302跳转到指定地址
+    pub fn redirect(location: impl Into<String>) -> Self {
+        Self::builder()
+            .status(StatusCode::FOUND)
+            .header("Location", location)
+            .build()
     }
-    pub fn status_code(&self) -> &str {
-        &self.status_code
+    pub fn json<T: Serialize + ?Sized>(value: &T) -> Self {
+        Self::builder().json(value)
     }
-    pub fn status_text(&self) -> &str {
-        &self.status_text
+    pub fn html(body: impl Into<String>) -> Self {
+        Self::builder().html(body)
+    }
+    pub fn text(body: impl Into<String>) -> Self {
+        Self::builder().text(body)
+    }
+    pub fn no_content() -> Self {
+        Self::builder().status(StatusCode::NO_CONTENT).build()
+    }
+    pub fn send_response<T: Write>(self, write_stream: &mut T) -> Result<(), std::io::Error> {
+        self.write_to(write_stream, &Method::GET)
     }
-    pub fn header(&self) -> String {
-        let map = self.headers.clone().unwrap();
-        let mut header_string: String = "".into();
-        for (k, v) in map.iter() {
-            header_string = format!("{}{}:{}\r\n", header_string, k, v);
+    //按RFC 9112 的规则写出响应,HEAD请求只写响应头
+    pub fn write_to<T: Write>(self, write_stream: &mut T, req_method: &Method) -> io::Result<()> {
+        let mut writer = BufWriter::new(write_stream);
+        write!(
+            writer,
+            "{} {} {}\r\n",
+            self.version,
+            self.status_code,
+            self.status_text()
+        )?;
+        //1xx/204/304 响应不能带响应体
+        let code = self.status_code;
+        let has_body = !(code.is_informational()
+            || code == StatusCode::NO_CONTENT
+            || code == StatusCode::NOT_MODIFIED);
+        //HEAD的处理函数可以不生成响应体,只用Content-Length给出资源的长度
+        let empty_body = match &self.body {
+            None => true,
+            Some(ResponseBody::Full(body)) => body.is_empty(),
+            Some(ResponseBody::Stream(_)) => false,
+        };
+        let head_length = *req_method == Method::HEAD
+            && empty_body
+            && self.headers.contains_key("Content-Length");
+        for (k, v) in self.headers.iter() {
+            //长度相关的字段由序列化时决定,304可以保留原资源的长度
+            let is_length = k.eq_ignore_ascii_case("Content-Length");
+            let is_framing = is_length || k.eq_ignore_ascii_case("Transfer-Encoding");
+            let keep = code == StatusCode::NOT_MODIFIED || (head_length && is_length);
+            if is_framing && !keep {
+                continue;
+            }
+            write!(writer, "{}: {}\r\n", k, v)?;
+        }
+        if !self.headers.contains_key("Date") {
+            write!(writer, "Date: {}\r\n", http_date(SystemTime::now()))?;
+        }
+        if !self.headers.contains_key("Server") {
+            write!(writer, "Server: {}\r\n", SERVER_NAME)?;
         }
-        header_string
+        let write_body = has_body && *req_method != Method::HEAD;
+        match self.body {
+            //1.0没有chunked编码,流式响应体以关闭连接作为结束
+            Some(ResponseBody::Stream(stream)) if self.version == Version::V1_0 => {
+                writer.write_all(b"\r\n")?;
+                if write_body {
+                    for chunk in stream {
+                        writer.write_all(&chunk?)?;
+                    }
+                }
+            }
+            Some(ResponseBody::Stream(stream)) => {
+                if has_body {
+                    writer.write_all(b"Transfer-Encoding: chunked\r\n")?;
+                }
+                writer.write_all(b"\r\n")?;
+                if write_body {
+                    let mut encoder = ChunkedEncoder::new(&mut writer);
+                    for chunk in stream {
+                        encoder.write_all(&chunk?)?;
+                    }
+                    encoder.finish()?;
+                }
+            }
+            body => {
+                let body = match &body {
+                    Some(ResponseBody::Full(body)) => body.as_slice(),
+                    _ => &[],
+                };
+                if has_body && !head_length {
+                    write!(writer, "Content-Length: {}\r\n", body.len())?;
+                }
+                writer.write_all(b"\r\n")?;
+                if write_body {
+                    writer.write_all(body)?;
+                }
+            }
+        }
+        writer.flush()
+    }
+    pub fn version(&self) -> Version {
+        self.version
+    }
+    //按客户端的版本回复,1.0的客户端不认识chunked编码
+    pub fn set_version(&mut self, version: Version) {
+        self.version = version;
+    }
+    pub fn headers(&self) -> &HeaderMap {
+        &self.headers
+    }
+    pub fn headers_mut(&mut self) -> &mut HeaderMap {
+        &mut self.headers
+    }
+    pub fn status_code(&self) -> StatusCode {
+        self.status_code
     }
-    pub fn body(&self) -> &str {
+    pub fn status_text(&self) -> &str {
+        self.status_code.reason_phrase().unwrap_or("")
+    }
+    //流式响应体返回空
+    pub fn body(&self) -> &[u8] {
         match &self.body {
-            None => "",
-            Some(val) => val.as_str(),
+            Some(ResponseBody::Full(val)) => val,
+            _ => &[],
+        }
+    }
+    pub fn is_stream(&self) -> bool {
+        matches!(self.body, Some(ResponseBody::Stream(_)))
+    }
+    //响应体按utf-8文本读取
+    pub fn body_text(&self) -> Result<&str, Utf8Error> {
+        std::str::from_utf8(self.body())
+    }
+}
+//格式化成HTTP-date(IMF-fixdate),如 Sun, 06 Nov 1994 08:49:37 GMT
+pub fn http_date(time: SystemTime) -> String {
+    const WEEKDAYS: [&str; 7] = ["Sun", "Mon", "Tue", "Wed", "Thu", "Fri", "Sat"];
+    const MONTHS: [&str; 12] = [
+        "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+    ];
+    let secs = time.duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+    let days = secs / 86400;
+    let secs_of_day = secs % 86400;
+    //1970-01-01 是星期四
+    let weekday = WEEKDAYS[((days + 4) % 7) as usize];
+    //把天数换算成公历日期
+    let z = days as i64 + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!(
+        "{}, {:02} {} {} {:02}:{:02}:{:02} GMT",
+        weekday,
+        day,
+        MONTHS[(month - 1) as usize],
+        year,
+        secs_of_day / 3600,
+        secs_of_day % 3600 / 60,
+        secs_of_day % 60
+    )
+}
+//链式构造响应,以设置响应体的方法结束
+#[derive(Debug, Default)]
+pub struct ResponseBuilder {
+    status_code: StatusCode,
+    headers: HeaderMap,
+}
+impl ResponseBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn status(mut self, status_code: StatusCode) -> Self {
+        self.status_code = status_code;
+        self
+    }
+    //追加响应头,同名字段可以有多个(如Set-Cookie)
+    pub fn header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.append(name, value);
+        self
+    }
+    pub fn build(self) -> HttpResponse {
+        HttpResponse {
+            status_code: self.status_code,
+            headers: self.headers,
+            ..HttpResponse::default()
+        }
+    }
+    pub fn body(self, body: impl Into<ResponseBody>) -> HttpResponse {
+        let mut response = self.build();
+        response.body = Some(body.into());
+        response
+    }
+    pub fn stream(self, body: BodyStream) -> HttpResponse {
+        self.body(body)
+    }
+    //序列化失败时返回500
+    pub fn json<T: Serialize + ?Sized>(self, value: &T) -> HttpResponse {
+        match serde_json::to_vec(value) {
+            Ok(body) => self.content_type("application/json").body(body),
+            Err(err) => HttpResponse::builder()
+                .status(StatusCode::INTERNAL_SERVER_ERROR)
+                .text(err.to_string()),
         }
     }
+    pub fn html(self, body: impl Into<String>) -> HttpResponse {
+        self.content_type("text/html; charset=utf-8")
+            .body(body.into())
+    }
+    pub fn text(self, body: impl Into<String>) -> HttpResponse {
+        self.content_type("text/plain; charset=utf-8")
+            .body(body.into())
+    }
+    //已经设置过Content-Type时不覆盖
+    fn content_type(mut self, value: &str) -> Self {
+        if !self.headers.contains_key("Content-Type") {
+            self.headers.insert("Content-Type", value);
+        }
+        self
+    }
 }
 #[cfg(test)]
 mod tests {
     use super::*;
     #[test]
     fn test_response_struct_creation_200() {
-        let res = HttpResponse::new("200", None, Some("xxxx".into()));
+        let res = HttpResponse::new(StatusCode::OK, None, Some("xxxx".into()));
         let res_expected = HttpResponse {
-            version: "HTTP/1.1",
-            status_code: "200",
-            status_text: "OK",
+            version: Version::V1_1,
+            status_code: StatusCode::OK,
             headers: {
-                let mut h = HashMap::new();
+                let mut h = HeaderMap::new();
                 h.insert("Content-Type", "text/html");
-                Some(h)
+                h
             },
             body: Some("xxxx".into()),
         };
@@ -109,15 +506,14 @@ mod tests {
     }
     #[test]
     fn test_response_struct_creation_404() {
-        let res = HttpResponse::new("404", None, Some("xxxx".into()));
+        let res = HttpResponse::new(StatusCode::NOT_FOUND, None, Some("xxxx".into()));
         let res_expected = HttpResponse {
-            version: "HTTP/1.1",
-            status_code: "404",
-            status_text: "Not Found",
+            version: Version::V1_1,
+            status_code: StatusCode::NOT_FOUND,
             headers: {
-                let mut h = HashMap::new();
+                let mut h = HeaderMap::new();
                 h.insert("Content-Type", "text/html");
-                Some(h)
+                h
             },
             body: Some("xxxx".into()),
         };
@@ -126,17 +522,181 @@ mod tests {
     #[test]
     fn test_http_response_creation() {
         let res_expected = HttpResponse {
-            version: "HTTP/1.1",
-            status_code: "404",
-            status_text: "Not Found",
+            version: Version::V1_1,
+            status_code: StatusCode::NOT_FOUND,
             headers: {
-                let mut h = HashMap::new();
+                let mut h = HeaderMap::new();
                 h.insert("Content-Type", "text/html");
-                Some(h)
+                h
             },
             body: Some("xxxx".into()),
         };
-        let res_str: String = res_expected.into();
-        println!("{}", res_str);
+        let res_bytes: Vec<u8> = res_expected.into();
+        println!("{}", String::from_utf8_lossy(&res_bytes));
+    }
+    #[test]
+    fn test_http_response_binary_body() {
+        let res = HttpResponse::new(StatusCode::OK, None, Some(vec![0x89, b'P', 0xff]));
+        assert!(res.body_text().is_err());
+        let res_bytes: Vec<u8> = res.into();
+        assert!(res_bytes.ends_with(b"Content-Length: 3\r\n\r\n\x89P\xff"));
+    }
+    #[test]
+    fn test_http_response_stream() {
+        let res = HttpResponse::new_stream(
+            StatusCode::OK,
+            None,
+            BodyStream::from_chunks(["id,name\n", "1,apple\n"]),
+        );
+        assert!(res.is_stream());
+        let res_bytes: Vec<u8> = res.into();
+        let res_str = String::from_utf8(res_bytes).unwrap();
+        assert!(!res_str.contains("Content-Length"));
+        assert!(res_str.ends_with(
+            "Transfer-Encoding: chunked\r\n\r\n8\r\nid,name\n\r\n8\r\n1,apple\n\r\n0\r\n\r\n"
+        ));
+        let res = HttpResponse::new_stream(
+            StatusCode::OK,
+            None,
+            BodyStream::from_reader(io::Cursor::new(vec![b'x'; 10000])),
+        );
+        let res_bytes: Vec<u8> = res.into();
+        let res_str = String::from_utf8(res_bytes).unwrap();
+        assert!(res_str.contains("\r\n2000\r\n"));
+        assert!(res_str.contains("\r\n710\r\n"));
+        let mut res = HttpResponse::new_stream(
+            StatusCode::OK,
+            None,
+            BodyStream::from_chunks(["id,name\n", "1,apple\n"]),
+        );
+        res.set_version(Version::V1_0);
+        let res_str = String::from_utf8(res.into()).unwrap();
+        assert!(res_str.starts_with("HTTP/1.0 200 OK\r\n"));
+        assert!(!res_str.contains("Transfer-Encoding"));
+        assert!(res_str.ends_with("\r\n\r\nid,name\n1,apple\n"));
+    }
+    #[test]
+    fn test_status_code() {
+        assert_eq!(StatusCode::HTTP_VERSION_NOT_SUPPORTED.as_u16(), 505);
+        assert_eq!(
+            StatusCode::HTTP_VERSION_NOT_SUPPORTED.reason_phrase(),
+            Some("HTTP Version Not Supported")
+        );
+        assert_eq!(
+            StatusCode::INTERNAL_SERVER_ERROR.reason_phrase(),
+            Some("Internal Server Error")
+        );
+        assert_eq!(StatusCode::from_u16(201), Some(StatusCode::CREATED));
+        assert_eq!(StatusCode::from_u16(299).unwrap().reason_phrase(), None);
+        assert_eq!(StatusCode::from_u16(99), None);
+        assert_eq!(StatusCode::try_from(1000), Err(1000));
+        assert!(StatusCode::CONTINUE.is_informational());
+        assert!(StatusCode::NO_CONTENT.is_success());
+        assert!(StatusCode::MOVED_PERMANENTLY.is_redirection());
+        assert!(StatusCode::FORBIDDEN.is_client_error());
+        assert!(!StatusCode::FORBIDDEN.is_server_error());
+        assert!(StatusCode::SERVICE_UNAVAILABLE.is_server_error());
+        assert_eq!(StatusCode::NOT_FOUND.to_string(), "404");
+    }
+    #[test]
+    fn test_http_response_status_line() {
+        let res = HttpResponse::new(StatusCode::HTTP_VERSION_NOT_SUPPORTED, None, None);
+        let res_bytes: Vec<u8> = res.into();
+        assert!(res_bytes.starts_with(b"HTTP/1.1 505 HTTP Version Not Supported\r\n"));
+    }
+    #[test]
+    fn test_response_builder() {
+        let etag = format!("\"{}\"", 42);
+        let res = HttpResponse::builder()
+            .status(StatusCode::CREATED)
+            .header("ETag", etag)
+            .header("Set-Cookie", "a=1")
+            .header("Set-Cookie", "b=2")
+            .json(&vec![1, 2, 3]);
+        assert_eq!(res.status_code(), StatusCode::CREATED);
+        assert_eq!(res.headers().get("etag"), Some("\"42\""));
+        assert_eq!(res.headers().get_all("set-cookie").count(), 2);
+        assert_eq!(res.headers().get("Content-Type"), Some("application/json"));
+        assert_eq!(res.body(), b"[1,2,3]");
+        let res = HttpResponse::builder()
+            .header("Content-Type", "text/csv")
+            .text("a,b");
+        assert_eq!(res.headers().get("Content-Type"), Some("text/csv"));
+    }
+    #[test]
+    fn test_response_shortcuts() {
+        let res = HttpResponse::redirect(format!("/orders/{}", 7));
+        assert_eq!(res.status_code(), StatusCode::FOUND);
+        assert_eq!(res.headers().get("Location"), Some("/orders/7"));
+        let res = HttpResponse::html("<p>hi</p>");
+        assert_eq!(
+            res.headers().get("Content-Type"),
+            Some("text/html; charset=utf-8")
+        );
+        assert_eq!(res.body(), b"<p>hi</p>");
+        let res = HttpResponse::text("hi");
+        assert_eq!(
+            res.headers().get("Content-Type"),
+            Some("text/plain; charset=utf-8")
+        );
+        let res = HttpResponse::json(&serde_json::json!({"id": 1}));
+        assert_eq!(res.body_text().unwrap(), "{\"id\":1}");
+        let res = HttpResponse::no_content();
+        assert_eq!(res.status_code(), StatusCode::NO_CONTENT);
+        assert!(res.body().is_empty());
+    }
+    #[test]
+    fn test_http_date() {
+        let time = UNIX_EPOCH + std::time::Duration::from_secs(784111777);
+        assert_eq!(http_date(time), "Sun, 06 Nov 1994 08:49:37 GMT");
+        assert_eq!(http_date(UNIX_EPOCH), "Thu, 01 Jan 1970 00:00:00 GMT");
+        let time = UNIX_EPOCH + std::time::Duration::from_secs(951782400);
+        assert_eq!(http_date(time), "Tue, 29 Feb 2000 00:00:00 GMT");
+    }
+    #[test]
+    fn test_http_response_serialize() {
+        let res = HttpResponse::builder()
+            .header("X-B", "2")
+            .header("X-A", "1")
+            .header("Date", "Thu, 01 Jan 1970 00:00:00 GMT")
+            .text("hi");
+        let res_bytes: Vec<u8> = res.into();
+        assert_eq!(
+            String::from_utf8(res_bytes).unwrap(),
+            format!(
+                "HTTP/1.1 200 OK\r\nX-B: 2\r\nX-A: 1\r\nDate: Thu, 01 Jan 1970 00:00:00 GMT\r\n\
+                 Content-Type: text/plain; charset=utf-8\r\nServer: {}\r\nContent-Length: 2\r\n\r\nhi",
+                SERVER_NAME
+            )
+        );
+    }
+    #[test]
+    fn test_http_response_framing() {
+        let res = HttpResponse::builder()
+            .status(StatusCode::NO_CONTENT)
+            .body("ignored");
+        let res_str = String::from_utf8(res.into()).unwrap();
+        assert!(!res_str.contains("Content-Length"));
+        assert!(res_str.ends_with("\r\n\r\n"));
+        let res = HttpResponse::builder()
+            .status(StatusCode::NOT_MODIFIED)
+            .header("Content-Length", "120")
+            .build();
+        let res_str = String::from_utf8(res.into()).unwrap();
+        assert!(res_str.contains("\r\nContent-Length: 120\r\n"));
+        assert!(res_str.ends_with("\r\n\r\n"));
+        let mut res_bytes = Vec::new();
+        HttpResponse::text("hello")
+            .write_to(&mut res_bytes, &Method::HEAD)
+            .unwrap();
+        let res_str = String::from_utf8(res_bytes).unwrap();
+        assert!(res_str.ends_with("Content-Length: 5\r\n\r\n"));
+        let mut res_bytes = Vec::new();
+        HttpResponse::builder()
+            .stream(BodyStream::from_chunks(["a"]))
+            .write_to(&mut res_bytes, &Method::HEAD)
+            .unwrap();
+        let res_str = String::from_utf8(res_bytes).unwrap();
+        assert!(res_str.ends_with("Transfer-Encoding: chunked\r\n\r\n"));
     }
 }
@@ -0,0 +1,250 @@
+use std::fmt;
+
+//解析后的查询字符串,保留原始内容和解码后的键值对
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Query {
+    raw: String,
+    pairs: Vec<(String, String)>,
+}
+impl Query {
+    pub fn parse(raw: &str) -> Self {
+        let pairs = raw
+            .split('&')
+            .filter(|pair| !pair.is_empty())
+            .map(|pair| {
+                let (key, val) = pair.split_once('=').unwrap_or((pair, ""));
+                (decode(key, true), decode(val, true))
+            })
+            .collect();
+        Self {
+            raw: raw.to_string(),
+            pairs,
+        }
+    }
+    //取第一个同名参数的值
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.pairs
+            .iter()
+            .find(|(k, _)| k == name)
+            .map(|(_, v)| v.as_str())
+    }
+    pub fn pairs(&self) -> &[(String, String)] {
+        &self.pairs
+    }
+    pub fn as_str(&self) -> &str {
+        &self.raw
+    }
+    pub fn is_empty(&self) -> bool {
+        self.raw.is_empty()
+    }
+}
+//绝对形式的URI: scheme://authority/path?query
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Uri {
+    scheme: String,
+    authority: String,
+    raw_path: String,
+    path: String,
+    query: Query,
+}
+impl Uri {
+    pub fn parse(s: &str) -> Option<Self> {
+        let (scheme, rest) = s.split_once("://")?;
+        let valid_scheme = scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+            && scheme
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || "+-.".contains(c));
+        if !valid_scheme {
+            return None;
+        }
+        //片段只在客户端使用,不会发给服务端
+        let rest = rest.split_once('#').map_or(rest, |(r, _)| r);
+        let authority_end = rest.find(['/', '?']).unwrap_or(rest.len());
+        let (authority, path_and_query) = rest.split_at(authority_end);
+        if authority.is_empty() || authority.contains(char::is_whitespace) {
+            return None;
+        }
+        let (raw_path, query) = path_and_query
+            .split_once('?')
+            .unwrap_or((path_and_query, ""));
+        let raw_path = if raw_path.is_empty() { "/" } else { raw_path };
+        Some(Self {
+            scheme: scheme.to_ascii_lowercase(),
+            authority: authority.to_string(),
+            raw_path: raw_path.to_string(),
+            path: percent_decode(raw_path),
+            query: Query::parse(query),
+        })
+    }
+    pub fn scheme(&self) -> &str {
+        &self.scheme
+    }
+    pub fn authority(&self) -> &str {
+        &self.authority
+    }
+    //authority中去掉用户信息和端口后的主机名
+    pub fn host(&self) -> &str {
+        self.split_host_port().0
+    }
+    pub fn port(&self) -> Option<u16> {
+        self.split_host_port().1.and_then(|port| port.parse().ok())
+    }
+    fn split_host_port(&self) -> (&str, Option<&str>) {
+        let host = self
+            .authority
+            .rsplit_once('@')
+            .map_or(self.authority.as_str(), |(_, h)| h);
+        if host.starts_with('[') {
+            //IPv6地址本身带冒号
+            match host.find(']') {
+                Some(i) => (&host[..=i], host[i + 1..].strip_prefix(':')),
+                None => (host, None),
+            }
+        } else {
+            match host.rsplit_once(':') {
+                Some((h, port)) => (h, Some(port)),
+                None => (host, None),
+            }
+        }
+    }
+    //解码后的路径
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+    //未解码的原始路径
+    pub fn raw_path(&self) -> &str {
+        &self.raw_path
+    }
+    pub fn query(&self) -> &Query {
+        &self.query
+    }
+    //转成origin形式的请求目标,保留原始编码
+    pub fn path_and_query(&self) -> String {
+        if self.query.is_empty() {
+            self.raw_path.clone()
+        } else {
+            format!("{}?{}", self.raw_path, self.query.as_str())
+        }
+    }
+}
+impl fmt::Display for Uri {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}://{}{}",
+            self.scheme,
+            self.authority,
+            self.path_and_query()
+        )
+    }
+}
+//authority形式: host:port,CONNECT请求使用
+pub fn is_authority(s: &str) -> bool {
+    match s.rsplit_once(':') {
+        Some((host, port)) => {
+            !host.is_empty()
+                && !host.contains(['/', '?', '#', '@'])
+                && !host.contains(char::is_whitespace)
+                && port.parse::<u16>().is_ok()
+        }
+        None => false,
+    }
+}
+//百分号解码,不合法的转义原样保留
+pub fn percent_decode(s: &str) -> String {
+    decode(s, false)
+}
+//路径的百分号编码,保留路径中允许出现的字符
+pub fn percent_encode_path(s: &str) -> String {
+    let mut encoded = String::with_capacity(s.len());
+    for b in s.bytes() {
+        if b.is_ascii_alphanumeric() || b"/-._~!$&'()*+,;=:@".contains(&b) {
+            encoded.push(b as char);
+        } else {
+            encoded.push_str(&format!("%{:02X}", b));
+        }
+    }
+    encoded
+}
+fn decode(s: &str, plus_as_space: bool) -> String {
+    let bytes = s.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'%' if i + 2 < bytes.len() => {
+                match (hex_value(bytes[i + 1]), hex_value(bytes[i + 2])) {
+                    (Some(h), Some(l)) => {
+                        decoded.push(h << 4 | l);
+                        i += 3;
+                        continue;
+                    }
+                    _ => decoded.push(b'%'),
+                }
+            }
+            b'+' if plus_as_space => decoded.push(b' '),
+            b => decoded.push(b),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+fn hex_value(b: u8) -> Option<u8> {
+    match b {
+        b'0'..=b'9' => Some(b - b'0'),
+        b'a'..=b'f' => Some(b - b'a' + 10),
+        b'A'..=b'F' => Some(b - b'A' + 10),
+        _ => None,
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn test_percent_decode() {
+        assert_eq!(percent_decode("/a%20b/%E4%BD%A0"), "/a b/你");
+        assert_eq!(percent_decode("/100%"), "/100%");
+        assert_eq!(percent_decode("/%zz/a+b"), "/%zz/a+b");
+    }
+    #[test]
+    fn test_percent_encode_path() {
+        assert_eq!(percent_encode_path("/a b/你"), "/a%20b/%E4%BD%A0");
+        assert_eq!(percent_encode_path("/x-1_y.z~/@:"), "/x-1_y.z~/@:");
+        assert_eq!(percent_decode(&percent_encode_path("/100%?#")), "/100%?#");
+    }
+    #[test]
+    fn test_uri_parse() {
+        let uri = Uri::parse("http://user@example.com:8080/a%20b?x=1#frag").unwrap();
+        assert_eq!(uri.scheme(), "http");
+        assert_eq!(uri.authority(), "user@example.com:8080");
+        assert_eq!(uri.host(), "example.com");
+        assert_eq!(uri.port(), Some(8080));
+        assert_eq!(uri.path(), "/a b");
+        assert_eq!(uri.query().get("x"), Some("1"));
+        assert_eq!(uri.path_and_query(), "/a%20b?x=1");
+        let uri = Uri::parse("HTTP://example.com").unwrap();
+        assert_eq!(uri.to_string(), "http://example.com/");
+        assert_eq!(uri.port(), None);
+        assert_eq!(Uri::parse("http://[::1]:80/").unwrap().host(), "[::1]");
+        assert!(Uri::parse("/relative").is_none());
+        assert!(Uri::parse("1http://x/").is_none());
+        assert!(Uri::parse("http:///path").is_none());
+    }
+    #[test]
+    fn test_is_authority() {
+        assert!(is_authority("example.com:443"));
+        assert!(!is_authority("example.com"));
+        assert!(!is_authority("example.com:https"));
+        assert!(!is_authority("/path:1"));
+    }
+    #[test]
+    fn test_query_parse() {
+        let query = Query::parse("id=5&name=a+b%26c&flag&id=6");
+        assert_eq!(query.get("id"), Some("5"));
+        assert_eq!(query.get("name"), Some("a b&c"));
+        assert_eq!(query.get("flag"), Some(""));
+        assert_eq!(query.get("missing"), None);
+        assert_eq!(query.pairs().len(), 4);
+        assert_eq!(query.as_str(), "id=5&name=a+b%26c&flag&id=6");
+    }
+}
@@ -1,2 +1,6 @@
+pub mod http_chunked;
+pub mod http_header;
+pub mod http_parser;
 pub mod http_request;
 pub mod http_response;
+pub mod http_uri;
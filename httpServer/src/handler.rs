@@ -1,18 +1,52 @@
 use http::{
-    http_request::{HttpRequest, Resource},
-    http_response::HttpResponse,
+    http_header::HeaderMap,
+    http_request::HttpRequest,
+    http_response::{HttpResponse, StatusCode},
 };
 use serde::{Deserialize, Serialize};
-use std::{collections::HashMap, env, fs};
-fn load_file(file_name: &str) -> Option<String> {
+use std::{env, fs};
+//按字节读取,图片字体等二进制文件也能返回
+fn load_file(file_name: &str) -> Option<Vec<u8>> {
     let default_path: String = format!("{}/public", env!("CARGO_MANIFEST_DIR"));
     let public_path = env::var("PUBLIC_PATH").unwrap_or(default_path);
     let full_path: String = format!("{}/{}", public_path, file_name);
-    let contents = fs::read_to_string(full_path);
+    let contents = fs::read(full_path);
     contents.ok()
 }
-pub trait Handler {
-    fn handle(req: &HttpRequest) -> HttpResponse;
+//根据文件后缀确定Content-Type
+fn content_type(file_name: &str) -> &'static str {
+    let extension = file_name.rsplit_once('.').map_or("", |(_, ext)| ext);
+    match extension.to_ascii_lowercase().as_str() {
+        "css" => "text/css",
+        "js" => "text/javascript",
+        "json" => "application/json",
+        "txt" => "text/plain",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "svg" => "image/svg+xml",
+        "ico" => "image/x-icon",
+        "webp" => "image/webp",
+        "woff" => "font/woff",
+        "woff2" => "font/woff2",
+        "ttf" => "font/ttf",
+        "otf" => "font/otf",
+        "pdf" => "application/pdf",
+        _ => "text/html",
+    }
+}
+//请求处理器,可以带状态,会被多个连接线程同时调用
+pub trait Handler: Send + Sync {
+    fn handle(&self, req: &HttpRequest) -> HttpResponse;
+}
+//闭包和函数直接当作处理器使用
+impl<F> Handler for F
+where
+    F: Fn(&HttpRequest) -> HttpResponse + Send + Sync,
+{
+    fn handle(&self, req: &HttpRequest) -> HttpResponse {
+        self(req)
+    }
 }
 pub struct WebServiceHandler;
 pub struct StaticPageHandler;
@@ -24,30 +58,23 @@ pub struct OrderStatus {
     order_date: String,
 }
 impl Handler for PageNotFoundHandler {
-    fn handle(_req: &HttpRequest) -> HttpResponse {
-        HttpResponse::new("404", None, load_file("404.html"))
+    fn handle(&self, _req: &HttpRequest) -> HttpResponse {
+        HttpResponse::new(StatusCode::NOT_FOUND, None, load_file("404.html"))
     }
 }
 impl Handler for StaticPageHandler {
-    fn handle(req: &HttpRequest) -> HttpResponse {
-        let http::http_request::Resource::Path(s) = &req.resource;
-        let route: Vec<_> = s.split("/").collect();
+    fn handle(&self, req: &HttpRequest) -> HttpResponse {
+        let route: Vec<_> = req.path().split("/").collect();
         match route[1] {
-            "" => HttpResponse::new("200", None, load_file("index.html")),
-            "health" => HttpResponse::new("200", None, load_file("health.html")),
+            "" => HttpResponse::new(StatusCode::OK, None, load_file("index.html")),
+            "health" => HttpResponse::new(StatusCode::OK, None, load_file("health.html")),
             path => match load_file(path) {
                 Some(content) => {
-                    let mut map = HashMap::new();
-                    if path.ends_with(".css") {
-                        map.insert("Content-Type", "text/css");
-                    } else if path.ends_with(".js") {
-                        map.insert("Content-Type", "text/javascript");
-                    } else {
-                        map.insert("Content-Type", "text/html");
-                    }
-                    HttpResponse::new("200", Some(map), Some(content))
+                    let mut map = HeaderMap::new();
+                    map.insert("Content-Type", content_type(path));
+                    HttpResponse::new(StatusCode::OK, Some(map), Some(content))
                 }
-                None => PageNotFoundHandler::handle(req),
+                None => PageNotFoundHandler.handle(req),
             },
         }
     }
@@ -62,17 +89,13 @@ impl WebServiceHandler {
     }
 }
 impl Handler for WebServiceHandler {
-    fn handle(req: &HttpRequest) -> HttpResponse {
-        let Resource::Path(s) = &req.resource;
-        let route: Vec<_> = s.split("/").collect();
+    fn handle(&self, req: &HttpRequest) -> HttpResponse {
+        let route: Vec<_> = req.path().split("/").collect();
         match route[2] {
             "shipping" if route.len() > 2 && route[3] == "orders" => {
-                let body = Some(serde_json::to_string(&Self::load_json()).unwrap());
-                let mut header = HashMap::new();
-                header.insert("Content-Type", "application/json");
-                HttpResponse::new("200", Some(header), body)
+                HttpResponse::json(&Self::load_json())
             }
-            _ => PageNotFoundHandler::handle(req),
+            _ => PageNotFoundHandler.handle(req),
         }
     }
 }
@@ -1,4 +1,4 @@
-use http::http_response::HttpResponse;
+use http::http_response::{HttpResponse, StatusCode};
 use server::Server;
 
 pub mod handler;
@@ -7,11 +7,14 @@ pub mod server;
 fn main() {
     let mut server_app = Server::new("localhost:9977");
     server_app.get("/ss".into(), |_req| {
-        HttpResponse::new("200", None, Some("Hello".into()))
+        HttpResponse::new(StatusCode::OK, None, Some("Hello".into()))
+    });
+    server_app.get("/orders/{id:u32}".into(), |req| {
+        HttpResponse::text(format!("order {}", req.param("id").unwrap_or_default()))
     });
     let mut ss_group = server_app.create_group("ss".into());
     ss_group.get("/path".into(), |_req| {
-        HttpResponse::new("200", None, Some("ok_group".into()))
+        HttpResponse::new(StatusCode::OK, None, Some("ok_group".into()))
     });
     server_app.run();
 }
@@ -1,36 +1,134 @@
-use std::{cell::RefCell, collections::HashMap, io::Write};
+use std::{
+    collections::HashMap,
+    io::{self, Write},
+    str::FromStr,
+    sync::Arc,
+};
 
 use http::{
-    http_request::{HttpRequest, Method, Resource},
-    http_response::HttpResponse,
+    http_request::{HttpRequest, Method, Resource, Version},
+    http_response::{HttpResponse, StatusCode},
 };
 
+use regex::Regex;
+
 use crate::handler::{Handler, PageNotFoundHandler};
 
+//注册后的请求处理器,路由表克隆到各个连接线程时共享同一个处理器
+pub type HandlerFunc = Arc<dyn Handler>;
+//按类型检查参数值的函数
+type TypeCheck = fn(&str) -> bool;
+//参数段的约束: {id:u32}按类型解析,{name:[a-z]+}按正则整段匹配
+#[derive(Clone)]
+enum Constraint {
+    Any,
+    Type(TypeCheck),
+    Regex(Regex),
+}
+//能按类型解析时才匹配
+fn parses<T: FromStr>(value: &str) -> bool {
+    value.parse::<T>().is_ok()
+}
+impl Constraint {
+    fn parse(pattern: &str, path: &str) -> Self {
+        const TYPES: [(&str, TypeCheck); 16] = [
+            ("u8", parses::<u8>),
+            ("u16", parses::<u16>),
+            ("u32", parses::<u32>),
+            ("u64", parses::<u64>),
+            ("u128", parses::<u128>),
+            ("usize", parses::<usize>),
+            ("i8", parses::<i8>),
+            ("i16", parses::<i16>),
+            ("i32", parses::<i32>),
+            ("i64", parses::<i64>),
+            ("i128", parses::<i128>),
+            ("isize", parses::<isize>),
+            ("f32", parses::<f32>),
+            ("f64", parses::<f64>),
+            ("bool", parses::<bool>),
+            ("char", parses::<char>),
+        ];
+        if let Some((_, check)) = TYPES.iter().find(|(ty, _)| *ty == pattern) {
+            return Self::Type(*check);
+        }
+        match Regex::new(&format!("^(?:{})$", pattern)) {
+            Ok(regex) => Self::Regex(regex),
+            Err(err) => panic!("bad param pattern in {}: {}", path, err),
+        }
+    }
+    fn check(&self, value: &str) -> bool {
+        match self {
+            Self::Any => true,
+            Self::Type(check) => check(value),
+            Self::Regex(regex) => regex.is_match(value),
+        }
+    }
+}
+//参数段: name是参数名,pattern是约束的原文,用来判断两次注册是不是同一个参数段
+#[derive(Clone)]
+struct Param {
+    name: String,
+    pattern: String,
+    constraint: Constraint,
+}
+impl Param {
+    //:name 或 {name} 或 {name:约束},不是参数段时返回None
+    fn parse(segment: &str, path: &str) -> Option<Self> {
+        let (name, pattern) = match segment.strip_prefix(':') {
+            Some(name) => (name, None),
+            None => {
+                let inner = segment.strip_prefix('{')?;
+                //路径按/切分,约束里带/时花括号会落在两个段里
+                let Some(inner) = inner.strip_suffix('}') else {
+                    panic!("bad param pattern in {}: unclosed {{ or / in pattern", path);
+                };
+                match inner.split_once(':') {
+                    Some((name, pattern)) => (name, Some(pattern)),
+                    None => (inner, None),
+                }
+            }
+        };
+        if name.is_empty() {
+            panic!("param segment needs a name: {}", path);
+        }
+        Some(Self {
+            name: name.to_string(),
+            pattern: pattern.unwrap_or("").to_string(),
+            constraint: pattern.map_or(Constraint::Any, |p| Constraint::parse(p, path)),
+        })
+    }
+}
 //路由树
 #[derive(Clone)]
 struct RouteTree {
-    handler_func: Option<fn(&http::http_request::HttpRequest) -> HttpResponse>, //节点对应的请求处理函数
-    children: HashMap<String, RouteTree>,                                       //子树
+    handler_func: Option<HandlerFunc>,       //节点对应的请求处理函数
+    children: HashMap<String, RouteTree>,    //静态子树
+    params: Vec<(Param, RouteTree)>,         //参数子树,有约束的排在没约束的前面
+    wildcard: Option<(String, HandlerFunc)>, //末尾的*rest
 }
 impl RouteTree {
     pub fn new() -> Self {
         Self {
             handler_func: None,
             children: HashMap::new(),
+            params: Vec::new(),
+            wildcard: None,
         }
     }
     pub fn root() -> Self {
         Self::new()
     }
+    fn is_empty(&self) -> bool {
+        self.handler_func.is_none()
+            && self.children.is_empty()
+            && self.params.is_empty()
+            && self.wildcard.is_none()
+    }
     //路由注册
-    fn regis_route(
-        &mut self,
-        path: String,
-        handler_func: fn(&http::http_request::HttpRequest) -> HttpResponse,
-    ) {
+    fn regis_route(&mut self, path: String, handler_func: HandlerFunc) {
         let mut current_node = self;
-        if path == "" || path == "/" {
+        if path.is_empty() || path == "/" {
             //特殊情况的匹配对根节点进行处理
             match current_node.handler_func {
                 None => {
@@ -38,145 +136,534 @@ impl RouteTree {
                 }
                 _ => panic!("repeat regis"),
             }
-            return;
         } else {
             let path_list: Vec<_> = path.split("/").collect();
-            match path_list.len() {
-                0 | 1 => {
+            if path_list.len() < 2 {
+                return;
+            }
+            let segments = &path_list[1..];
+            for (i, segment) in segments.iter().enumerate() {
+                if let Some(name) = segment.strip_prefix('*') {
+                    //通配段匹配剩下的所有路径,只能放在最后
+                    if name.is_empty() || i + 1 != segments.len() {
+                        panic!("wildcard must be the named last segment: {}", path);
+                    }
+                    if current_node.wildcard.is_some() {
+                        panic!("repeat regis");
+                    }
+                    current_node.wildcard = Some((name.to_string(), handler_func));
                     return;
                 }
-                _ => {
-                    //如果是是一个合理的路由
-                    let mut last_match = 0;
-                    for letter_counter in 1..path_list.len() {
-                        //先往下探测以及有的路由
-                        if current_node
-                            .children
-                            .contains_key(path_list[letter_counter])
+                //先往下探测已有的路由,没有的节点再创建
+                current_node = match Param::parse(segment, &path) {
+                    Some(param) => {
+                        let params = &mut current_node.params;
+                        let index = match params
+                            .iter()
+                            .position(|(p, _)| p.name == param.name && p.pattern == param.pattern)
                         {
-                            current_node = current_node
-                                .children
-                                .get_mut(path_list[letter_counter])
-                                .unwrap();
-                        } else {
-                            last_match = letter_counter;
-                            break;
-                        }
-                        last_match = letter_counter + 1;
+                            Some(index) => index,
+                            None => {
+                                //同一位置上先检查有约束的参数段
+                                let index = match param.constraint {
+                                    Constraint::Any => params.len(),
+                                    _ => params
+                                        .iter()
+                                        .position(|(p, _)| matches!(p.constraint, Constraint::Any))
+                                        .unwrap_or(params.len()),
+                                };
+                                params.insert(index, (param, RouteTree::new()));
+                                index
+                            }
+                        };
+                        &mut params[index].1
                     }
-                    if last_match == path_list.len() {
-                        //判断是否到达目的的路由
-                        match current_node.handler_func {
-                            None => current_node.handler_func = Some(handler_func),
-                            _ => panic!("repeat regis"),
-                        }
-                    } else {
-                        //否则再往下创建新的节点
-                        for new_counter in last_match..path_list.len() {
-                            current_node
-                                .children
-                                .insert((path_list[new_counter]).to_string(), RouteTree::new());
-                            current_node = current_node
-                                .children
-                                .get_mut(path_list[new_counter])
-                                .unwrap();
-                        }
-                        match current_node.handler_func {
-                            None => current_node.handler_func = Some(handler_func),
-                            _ => panic!("repeat regis"),
-                        }
-                    }
-                }
+                    None => current_node
+                        .children
+                        .entry(segment.to_string())
+                        .or_insert_with(RouteTree::new),
+                };
+            }
+            match current_node.handler_func {
+                None => current_node.handler_func = Some(handler_func),
+                _ => panic!("repeat regis"),
             }
         }
     }
-    //查询路由
+    //查询路由,匹配到的路径参数按顺序放进params
     fn find_handler(
         &self,
         path: String,
-    ) -> Option<fn(&http::http_request::HttpRequest) -> HttpResponse> {
-        if path == "" || path == "/" {
-            //特殊字符串获取根的路由
+        params: &mut Vec<(String, String)>,
+    ) -> Option<&HandlerFunc> {
+        if path.is_empty() || path == "/" {
+            //特殊字符串获取根的路由,没有时按一个空段交给根上的通配段
             self.handler_func
+                .as_ref()
+                .or_else(|| self.find_segments(&[""], params))
         } else {
             let path_list: Vec<_> = path.split("/").collect();
             match path_list.len() {
-                0 | 1 => return None, //不是合理的路由匹配字符串
-                _ => {
-                    //合理的路由匹配字符串
-                    let mut current_node = self;
-                    for index in 1..path_list.len() {
-                        //往下寻找
-                        if current_node.children.contains_key(path_list[index]) {
-                            current_node = current_node.children.get(path_list[index]).unwrap();
-                        } else {
-                            //没有了就返回node
-                            return None;
-                        }
-                    }
-                    return current_node.handler_func; //找到节点返回
+                0 | 1 => None,                                    //不是合理的路由匹配字符串
+                _ => self.find_segments(&path_list[1..], params), //合理的路由匹配字符串
+            }
+        }
+    }
+    //按 静态 > 参数 > 通配 的优先级往下匹配,走不通时回退尝试下一种
+    fn find_segments(
+        &self,
+        segments: &[&str],
+        params: &mut Vec<(String, String)>,
+    ) -> Option<&HandlerFunc> {
+        let Some((segment, rest)) = segments.split_first() else {
+            return self.handler_func.as_ref(); //找到节点返回
+        };
+        if let Some(handler) = self
+            .children
+            .get(*segment)
+            .and_then(|child| child.find_segments(rest, params))
+        {
+            return Some(handler);
+        }
+        if !segment.is_empty() {
+            //不满足约束的参数段直接跳过,处理函数不会拿到不合法的值
+            for (param, child) in &self.params {
+                if !param.constraint.check(segment) {
+                    continue;
                 }
+                params.push((param.name.clone(), segment.to_string()));
+                if let Some(handler) = child.find_segments(rest, params) {
+                    return Some(handler);
+                }
+                params.pop();
             }
         }
+        let (name, handler) = self.wildcard.as_ref()?;
+        params.push((name.clone(), segments.join("/")));
+        Some(handler)
     }
 }
 
+#[derive(Clone)]
 pub struct RouterMap {
-    tree_map: HashMap<Method, RefCell<RouteTree>>,
+    tree_map: HashMap<Method, RouteTree>,
+    any_tree: RouteTree, //不区分请求方法的路由
 }
+impl Default for RouterMap {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl RouterMap {
     pub fn new() -> Self {
         Self {
             tree_map: HashMap::new(),
+            any_tree: RouteTree::root(),
         }
     }
-    fn regis_route(
-        &mut self,
-        method: Method,
+    pub fn route<F>(&mut self, method: Method, path: String, handler_func: F)
+    where
+        F: Fn(&HttpRequest) -> HttpResponse + Send + Sync + 'static,
+    {
+        self.route_handler(method, path, handler_func);
+    }
+    //注册实现了Handler的带状态处理器
+    pub fn route_handler(&mut self, method: Method, path: String, handler: impl Handler + 'static) {
+        self.tree_map
+            .entry(method)
+            .or_insert_with(RouteTree::root)
+            .regis_route(path, Arc::new(handler));
+    }
+    pub fn get<F>(&mut self, path: String, handler_func: F)
+    where
+        F: Fn(&HttpRequest) -> HttpResponse + Send + Sync + 'static,
+    {
+        self.route(Method::GET, path, handler_func);
+    }
+    pub fn post<F>(&mut self, path: String, handler_func: F)
+    where
+        F: Fn(&HttpRequest) -> HttpResponse + Send + Sync + 'static,
+    {
+        self.route(Method::POST, path, handler_func);
+    }
+    pub fn put<F>(&mut self, path: String, handler_func: F)
+    where
+        F: Fn(&HttpRequest) -> HttpResponse + Send + Sync + 'static,
+    {
+        self.route(Method::PUT, path, handler_func);
+    }
+    pub fn patch<F>(&mut self, path: String, handler_func: F)
+    where
+        F: Fn(&HttpRequest) -> HttpResponse + Send + Sync + 'static,
+    {
+        self.route(Method::PATCH, path, handler_func);
+    }
+    pub fn delete<F>(&mut self, path: String, handler_func: F)
+    where
+        F: Fn(&HttpRequest) -> HttpResponse + Send + Sync + 'static,
+    {
+        self.route(Method::DELETE, path, handler_func);
+    }
+    pub fn head<F>(&mut self, path: String, handler_func: F)
+    where
+        F: Fn(&HttpRequest) -> HttpResponse + Send + Sync + 'static,
+    {
+        self.route(Method::HEAD, path, handler_func);
+    }
+    pub fn options<F>(&mut self, path: String, handler_func: F)
+    where
+        F: Fn(&HttpRequest) -> HttpResponse + Send + Sync + 'static,
+    {
+        self.route(Method::OPTIONS, path, handler_func);
+    }
+    //任意方法都能匹配的路由,包括扩展方法
+    pub fn any<F>(&mut self, path: String, handler_func: F)
+    where
+        F: Fn(&HttpRequest) -> HttpResponse + Send + Sync + 'static,
+    {
+        self.any_handler(path, handler_func);
+    }
+    pub fn any_handler(&mut self, path: String, handler: impl Handler + 'static) {
+        self.any_tree.regis_route(path, Arc::new(handler));
+    }
+    //先找对应方法的路由,找不到再找any路由
+    fn lookup(
+        &self,
+        method: &Method,
         path: String,
-        handler_func: fn(&http::http_request::HttpRequest) -> HttpResponse,
-    ) {
-        match self.tree_map.get(&method) {
-            None => {
-                let mut new_tree = RouteTree::root();
-                new_tree.regis_route(path, handler_func);
-                self.tree_map.insert(method, RefCell::new(new_tree));
+        params: &mut Vec<(String, String)>,
+    ) -> Option<&HandlerFunc> {
+        if let Some(handler) = self
+            .tree_map
+            .get(method)
+            .and_then(|tree| tree.find_handler(path.clone(), params))
+        {
+            return Some(handler);
+        }
+        params.clear();
+        self.any_tree.find_handler(path, params)
+    }
+    //是否有路由能处理这个请求,包括自动处理的HEAD和OPTIONS
+    pub fn has_route(&self, method: &Method, path: String) -> bool {
+        self.lookup(method, path.clone(), &mut Vec::new()).is_some()
+            || match method {
+                Method::HEAD => self.lookup(&Method::GET, path, &mut Vec::new()).is_some(),
+                Method::OPTIONS => !self.allowed_methods(&path).is_empty(),
+                _ => false,
             }
-            Some(tree) => {
-                tree.borrow_mut().regis_route(path, handler_func);
+    }
+    //路径可以用哪些方法访问: 注册过的方法,有GET时加上HEAD,再加上OPTIONS
+    fn allowed_methods(&self, path: &str) -> Vec<Method> {
+        let methods = if self
+            .any_tree
+            .find_handler(path.to_string(), &mut Vec::new())
+            .is_some()
+        {
+            standard_methods()
+        } else {
+            self.tree_map
+                .iter()
+                .filter(|(_, tree)| {
+                    tree.find_handler(path.to_string(), &mut Vec::new())
+                        .is_some()
+                })
+                .map(|(method, _)| method.clone())
+                .collect()
+        };
+        with_implied_methods(methods)
+    }
+    //OPTIONS * 描述整个服务支持的方法
+    fn server_methods(&self) -> Vec<Method> {
+        //没有注册任何路由时服务本身也支持OPTIONS
+        let mut methods = vec![Method::OPTIONS];
+        methods.extend(self.tree_map.keys().cloned());
+        if !self.any_tree.is_empty() {
+            methods.extend(standard_methods());
+        }
+        with_implied_methods(methods)
+    }
+    fn execute_handler(&self, req: &mut HttpRequest, path: String) -> HttpResponse {
+        let mut params = Vec::new();
+        //显式注册的HEAD优先,没有时用GET的处理函数,响应体在写出时丢掉
+        let handler = self
+            .lookup(&req.method, path.clone(), &mut params)
+            .or_else(|| match req.method {
+                Method::HEAD => {
+                    params.clear();
+                    self.lookup(&Method::GET, path.clone(), &mut params)
+                }
+                _ => None,
+            });
+        req.params = params;
+        if let Some(handler) = handler {
+            return handler.handle(req);
+        }
+        let allowed = match (&req.method, &req.resource) {
+            (Method::OPTIONS, Resource::Asterisk) => self.server_methods(),
+            _ => self.allowed_methods(&path),
+        };
+        if allowed.is_empty() {
+            return PageNotFoundHandler.handle(req);
+        }
+        let allow: Vec<_> = allowed.iter().map(Method::as_str).collect();
+        //没有显式注册OPTIONS时列出允许的方法
+        if req.method == Method::OPTIONS {
+            return HttpResponse::builder()
+                .status(StatusCode::NO_CONTENT)
+                .header("Allow", allow.join(", "))
+                .build();
+        }
+        //路径存在但方法不对时返回405,而不是404
+        HttpResponse::builder()
+            .status(StatusCode::METHOD_NOT_ALLOWED)
+            .header("Allow", allow.join(", "))
+            .text("405 Method Not Allowed")
+    }
+    //处理请求并写出响应,返回连接是否还能继续使用
+    pub fn handle_req<T: Write>(
+        &self,
+        pre_path: &str,
+        req: &mut HttpRequest,
+        stream: &mut T,
+        keep_alive: bool,
+    ) -> io::Result<bool> {
+        //只用解码后的路径匹配路由,查询字符串不参与匹配
+        let path = format!("{}{}", pre_path, req.path());
+        let mut res = self.execute_handler(req, path);
+        //用客户端的版本回复,1.0的流式响应体只能靠关闭连接结束
+        res.set_version(req.version.min(Version::V1_1));
+        //处理函数自己要求关闭连接时也不再复用
+        let keep_alive = keep_alive
+            && !res.headers().has_token("Connection", "close")
+            && !(req.version == Version::V1_0 && res.is_stream());
+        if !keep_alive {
+            res.headers_mut().insert("Connection", "close");
+        } else if req.version == Version::V1_0 {
+            res.headers_mut().insert("Connection", "keep-alive");
+        }
+        //直接写入流,流式响应体不会整个缓存在内存里
+        res.write_to(stream, &req.method)?;
+        Ok(keep_alive)
+    }
+}
+//any路由可以处理的常用方法
+fn standard_methods() -> Vec<Method> {
+    vec![
+        Method::GET,
+        Method::POST,
+        Method::PUT,
+        Method::PATCH,
+        Method::DELETE,
+    ]
+}
+//补上自动处理的HEAD和OPTIONS,去重后按方法名排序
+fn with_implied_methods(mut methods: Vec<Method>) -> Vec<Method> {
+    if methods.is_empty() {
+        return methods;
+    }
+    if methods.contains(&Method::GET) {
+        methods.push(Method::HEAD);
+    }
+    methods.push(Method::OPTIONS);
+    methods.sort_by(|a, b| a.as_str().cmp(b.as_str()));
+    methods.dedup();
+    methods
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    fn find(
+        router: &RouterMap,
+        method: Method,
+        path: &str,
+    ) -> Option<(String, Vec<(String, String)>)> {
+        let req = HttpRequest::parse("GET / HTTP/1.1\r\n\r\n").unwrap();
+        let mut params = Vec::new();
+        let handler = router.lookup(&method, path.to_string(), &mut params)?;
+        Some((
+            handler.handle(&req).body_text().unwrap().to_string(),
+            params,
+        ))
+    }
+    #[test]
+    fn test_route_priority() {
+        let mut router = RouterMap::new();
+        router.get("/orders/new".into(), |_| HttpResponse::text("static"));
+        router.get("/orders/:id".into(), |_| HttpResponse::text("param"));
+        router.get("/orders/*rest".into(), |_| HttpResponse::text("wildcard"));
+        router.get("/a/:x/c".into(), |_| HttpResponse::text("a-param"));
+        router.get("/a/b/d".into(), |_| HttpResponse::text("a-static"));
+        let found = |path| find(&router, Method::GET, path);
+        assert_eq!(found("/orders/new").unwrap().0, "static");
+        assert_eq!(
+            found("/orders/42").unwrap(),
+            (
+                "param".to_string(),
+                vec![("id".to_string(), "42".to_string())]
+            )
+        );
+        assert_eq!(
+            found("/orders/42/items/7").unwrap(),
+            (
+                "wildcard".to_string(),
+                vec![("rest".to_string(), "42/items/7".to_string())]
+            )
+        );
+        //静态段走不通时回退到参数段
+        assert_eq!(
+            found("/a/b/c").unwrap().1,
+            [("x".to_string(), "b".to_string())]
+        );
+        assert_eq!(found("/a/b/d").unwrap().0, "a-static");
+        assert!(found("/orders").is_none());
+        assert!(find(&router, Method::POST, "/orders/42").is_none());
+        //根上的通配段也能匹配/,和/orders/匹配/orders/*rest一致
+        let mut router = RouterMap::new();
+        router.get("/*rest".into(), |_| HttpResponse::text("fallback"));
+        let found = |path| find(&router, Method::GET, path);
+        assert_eq!(
+            found("/").unwrap(),
+            (
+                "fallback".to_string(),
+                vec![("rest".to_string(), "".to_string())]
+            )
+        );
+        assert_eq!(
+            found("/x").unwrap().1,
+            [("rest".to_string(), "x".to_string())]
+        );
+    }
+    #[test]
+    fn test_route_constraints() {
+        let mut router = RouterMap::new();
+        router.get("/orders/{id:u32}".into(), |_| HttpResponse::text("id"));
+        router.get("/orders/:slug".into(), |_| HttpResponse::text("slug"));
+        router.get(r"/files/{name:[a-z]+\.txt}".into(), |_| {
+            HttpResponse::text("txt")
+        });
+        router.get("/files/{name:[a-z]{2}}/raw".into(), |_| {
+            HttpResponse::text("raw")
+        });
+        let found = |path| find(&router, Method::GET, path).map(|(body, _)| body);
+        assert_eq!(found("/orders/42").as_deref(), Some("id"));
+        assert_eq!(found("/orders/-1").as_deref(), Some("slug"));
+        assert_eq!(found("/orders/4294967296").as_deref(), Some("slug"));
+        assert_eq!(found("/files/notes.txt").as_deref(), Some("txt"));
+        assert_eq!(found("/files/ab/raw").as_deref(), Some("raw"));
+        assert_eq!(found("/files/Notes.txt"), None);
+        assert_eq!(found("/files/notes.txt.bak"), None);
+    }
+    #[test]
+    fn test_method_not_allowed() {
+        let mut router = RouterMap::new();
+        router.get("/orders/:id".into(), |_| HttpResponse::text("get"));
+        router.delete("/orders/:id".into(), |_| HttpResponse::no_content());
+        router.post("/orders".into(), |_| HttpResponse::text("post"));
+        let mut req = HttpRequest::parse("PUT /orders/7 HTTP/1.1\r\n\r\n").unwrap();
+        let res = router.execute_handler(&mut req, "/orders/7".into());
+        assert_eq!(res.status_code(), StatusCode::METHOD_NOT_ALLOWED);
+        assert_eq!(
+            res.headers().get("Allow"),
+            Some("DELETE, GET, HEAD, OPTIONS")
+        );
+        let res = router.execute_handler(&mut req, "/missing".into());
+        assert_eq!(res.status_code(), StatusCode::NOT_FOUND);
+    }
+    #[test]
+    fn test_stateful_handlers() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        //带状态的处理器,路由表克隆后仍然共享计数
+        struct Counter(AtomicUsize);
+        impl Handler for Counter {
+            fn handle(&self, _req: &HttpRequest) -> HttpResponse {
+                HttpResponse::text(format!("{}", self.0.fetch_add(1, Ordering::SeqCst) + 1))
             }
         }
+        let greeting = String::from("hi");
+        let mut router = RouterMap::new();
+        router.route_handler(Method::GET, "/count".into(), Counter(AtomicUsize::new(0)));
+        router.get("/greet/:name".into(), move |req| {
+            HttpResponse::text(format!("{} {}", greeting, req.param("name").unwrap()))
+        });
+        router.any_handler("/any".into(), PageNotFoundHandler);
+        //不写参数类型的闭包也能注册
+        router.any("/echo".into(), |req| {
+            HttpResponse::text(req.method.to_string())
+        });
+        router.route(Method::PUT, "/put".into(), |_req| HttpResponse::text("put"));
+        let cloned = router.clone();
+        assert_eq!(find(&router, Method::GET, "/count").unwrap().0, "1");
+        assert_eq!(find(&cloned, Method::GET, "/count").unwrap().0, "2");
+        let mut req = HttpRequest::parse("GET /greet/bob HTTP/1.1\r\n\r\n").unwrap();
+        let res = router.execute_handler(&mut req, "/greet/bob".into());
+        assert_eq!(res.body_text(), Ok("hi bob"));
+        assert!(router.has_route(&Method::DELETE, "/any".into()));
+        assert_eq!(find(&router, Method::PATCH, "/echo").unwrap().0, "GET");
+        assert_eq!(find(&router, Method::PUT, "/put").unwrap().0, "put");
     }
-    pub fn get(
-        &mut self,
-        path: String,
-        handler_func: fn(&http::http_request::HttpRequest) -> HttpResponse,
-    ) {
-        self.regis_route(Method::GET, path, handler_func);
+    #[test]
+    fn test_automatic_head_and_options() {
+        let mut router = RouterMap::new();
+        router.get("/page".into(), |_| HttpResponse::text("hello"));
+        router.post("/form".into(), |_| HttpResponse::text("posted"));
+        router.options("/form".into(), |_| HttpResponse::text("custom"));
+        router.get("/sized".into(), |_| HttpResponse::text("x".repeat(120)));
+        router.head("/sized".into(), |_| {
+            HttpResponse::builder()
+                .header("Content-Length", "120")
+                .build()
+        });
+        let run = |raw: &str| {
+            let mut req = HttpRequest::parse(raw).unwrap();
+            let path = req.path().to_string();
+            router.execute_handler(&mut req, path)
+        };
+        //HEAD走GET的处理函数,写出时只保留响应头
+        let res = run("HEAD /page HTTP/1.1\r\n\r\n");
+        let mut raw = Vec::new();
+        res.write_to(&mut raw, &Method::HEAD).unwrap();
+        let raw = String::from_utf8(raw).unwrap();
+        assert!(raw.contains("\r\nContent-Length: 5\r\n"));
+        assert!(raw.ends_with("\r\n\r\n"));
+        //显式注册的HEAD可以只给出长度,不生成响应体
+        let res = run("HEAD /sized HTTP/1.1\r\n\r\n");
+        let mut raw = Vec::new();
+        res.write_to(&mut raw, &Method::HEAD).unwrap();
+        let raw = String::from_utf8(raw).unwrap();
+        assert!(raw.contains("\r\nContent-Length: 120\r\n"));
+        assert!(!raw.contains("Content-Length: 0"));
+        let res = run("OPTIONS /page HTTP/1.1\r\n\r\n");
+        assert_eq!(res.status_code(), StatusCode::NO_CONTENT);
+        assert_eq!(res.headers().get("Allow"), Some("GET, HEAD, OPTIONS"));
+        assert_eq!(
+            run("OPTIONS /form HTTP/1.1\r\n\r\n").body_text(),
+            Ok("custom")
+        );
+        let res = run("OPTIONS * HTTP/1.1\r\n\r\n");
+        assert_eq!(res.headers().get("Allow"), Some("GET, HEAD, OPTIONS, POST"));
+        let mut req = HttpRequest::parse("OPTIONS * HTTP/1.1\r\n\r\n").unwrap();
+        let res = RouterMap::new().execute_handler(&mut req, "*".into());
+        assert_eq!(res.status_code(), StatusCode::NO_CONTENT);
+        assert_eq!(res.headers().get("Allow"), Some("OPTIONS"));
+        assert_eq!(
+            run("OPTIONS /missing HTTP/1.1\r\n\r\n").status_code(),
+            StatusCode::NOT_FOUND
+        );
     }
-    pub fn post(
-        &mut self,
-        path: String,
-        handler_func: fn(&http::http_request::HttpRequest) -> HttpResponse,
-    ) {
-        self.regis_route(Method::POST, path, handler_func);
-    }
-    fn execute_handler<'a>(&'a self, req: &'a HttpRequest, path: String) -> HttpResponse {
-        match self.tree_map.get(&req.method) {
-            None => PageNotFoundHandler::handle(req),
-            Some(tree) => match tree.borrow().find_handler(path) {
-                Some(handler) => handler(&req),
-                None => PageNotFoundHandler::handle(&req),
-            },
-        }
-    }
-    pub fn handle_req<T: Write>(&self, pre_path: &str, req: &HttpRequest, stream: &mut T) {
-        let info: String;
-        let Resource::Path(path) = &req.resource;
-        info = self
-            .execute_handler(req, format!("{}{}", pre_path, path.clone()))
-            .into();
-
-        stream.write(info.as_bytes()).unwrap();
+    #[test]
+    #[should_panic(expected = "bad param pattern")]
+    fn test_slash_in_pattern() {
+        RouterMap::new().get("/files/{p:a/b}".into(), |_| HttpResponse::text("x"));
+    }
+    #[test]
+    #[should_panic(expected = "bad param pattern")]
+    fn test_bad_constraint() {
+        RouterMap::new().get("/x/{id:[a-}".into(), |_| HttpResponse::no_content());
+    }
+    #[test]
+    #[should_panic(expected = "wildcard")]
+    fn test_wildcard_must_be_last() {
+        RouterMap::new().get("/files/*path/meta".into(), |_| HttpResponse::no_content());
     }
 }
@@ -1,12 +1,36 @@
-use std::{cell::RefCell, io::Read, net::TcpListener, rc::Rc};
+use std::{
+    cell::RefCell,
+    io::Read,
+    net::{TcpListener, TcpStream},
+    rc::Rc,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    thread,
+    time::Duration,
+};
 
-use http::http_request::HttpRequest;
+use http::{
+    http_parser::RequestParser,
+    http_request::{HttpRequest, Method, Version},
+    http_response::{HttpResponse, StatusCode},
+};
 
-use crate::router::RouterMap;
+use crate::{handler::Handler, router::RouterMap};
+//每个连接的限制
+#[derive(Clone, Copy)]
+struct ConnectionLimits {
+    keep_alive_timeout: Duration, //空闲连接等待下一个请求的时间
+    max_requests: usize,          //每个连接最多处理的请求数
+    max_body_size: usize,         //请求体的大小上限
+    max_connections: usize,       //同时处理的连接数上限,每个连接占用一个线程
+}
 pub struct Server<'a> {
     pre_path: String, //前置路由
     socket_addr: &'a str,
     router: Rc<RefCell<RouterMap>>,
+    limits: ConnectionLimits,
 }
 type ServerGroup<'a> = Server<'a>;
 impl<'a> Server<'a> {
@@ -15,47 +39,271 @@ impl<'a> Server<'a> {
             pre_path: "".into(),
             socket_addr,
             router: Rc::new(RefCell::new(RouterMap::new())),
+            limits: ConnectionLimits {
+                keep_alive_timeout: Duration::from_secs(5),
+                max_requests: 100,
+                max_body_size: 10 * 1024 * 1024,
+                max_connections: 256,
+            },
         }
     }
+    pub fn set_keep_alive_timeout(&mut self, timeout: Duration) {
+        self.limits.keep_alive_timeout = timeout;
+    }
+    pub fn set_max_requests(&mut self, max_requests: usize) {
+        self.limits.max_requests = max_requests.max(1);
+    }
+    //超过上限的请求体返回413,带Expect: 100-continue的请求在发送请求体之前就会被拒绝
+    pub fn set_max_body_size(&mut self, max_body_size: usize) {
+        self.limits.max_body_size = max_body_size;
+    }
+    //超过上限的新连接直接返回503并关闭
+    pub fn set_max_connections(&mut self, max_connections: usize) {
+        self.limits.max_connections = max_connections.max(1);
+    }
     //服务运行
     pub fn run(&mut self) {
         let connection_listener = TcpListener::bind(self.socket_addr).unwrap();
         println!("Http Server running on {}", self.socket_addr);
+        //路由注册已经结束,复制一份给各个连接线程共享
+        let router = Arc::new(self.router.borrow().clone());
+        let active = Arc::new(AtomicUsize::new(0));
         for stream in connection_listener.incoming() {
-            let mut stream = stream.unwrap();
+            let mut stream = match stream {
+                Ok(stream) => stream,
+                Err(err) => {
+                    println!("Failed to accept connection: {}", err);
+                    continue;
+                }
+            };
+            let Some(slot) = ConnectionSlot::acquire(&active, self.limits.max_connections) else {
+                //连接数已满,不再起新线程
+                let res = HttpResponse::builder()
+                    .status(StatusCode::SERVICE_UNAVAILABLE)
+                    .header("Retry-After", "1")
+                    .text("too many connections");
+                reject(&mut stream, res);
+                continue;
+            };
             println!("Connection established");
-            let mut read_buf = [0; 2000];
-            stream.read(&mut read_buf).unwrap();
-            let req: HttpRequest = String::from_utf8(read_buf.to_vec()).unwrap().into();
-            self.router
-                .borrow()
-                .handle_req(&self.pre_path, &req, &mut stream);
+            let router = router.clone();
+            let pre_path = self.pre_path.clone();
+            let limits = self.limits;
+            thread::spawn(move || {
+                handle_connection(stream, &router, &pre_path, limits);
+                drop(slot);
+            });
         }
     }
-    pub fn get(
-        &mut self,
-        path: String,
-        handler_func: fn(&HttpRequest) -> http::http_response::HttpResponse,
-    ) {
+    pub fn get<F>(&mut self, path: String, handler_func: F)
+    where
+        F: Fn(&HttpRequest) -> HttpResponse + Send + Sync + 'static,
+    {
         self.router
             .borrow_mut()
             .get(format!("{}{}", self.pre_path, path), handler_func)
     }
-    pub fn post(
-        &mut self,
-        path: String,
-        handler_func: fn(&HttpRequest) -> http::http_response::HttpResponse,
-    ) {
+    pub fn post<F>(&mut self, path: String, handler_func: F)
+    where
+        F: Fn(&HttpRequest) -> HttpResponse + Send + Sync + 'static,
+    {
         self.router
             .borrow_mut()
             .post(format!("{}{}", self.pre_path, path), handler_func)
     }
+    pub fn put<F>(&mut self, path: String, handler_func: F)
+    where
+        F: Fn(&HttpRequest) -> HttpResponse + Send + Sync + 'static,
+    {
+        self.router
+            .borrow_mut()
+            .put(format!("{}{}", self.pre_path, path), handler_func)
+    }
+    pub fn patch<F>(&mut self, path: String, handler_func: F)
+    where
+        F: Fn(&HttpRequest) -> HttpResponse + Send + Sync + 'static,
+    {
+        self.router
+            .borrow_mut()
+            .patch(format!("{}{}", self.pre_path, path), handler_func)
+    }
+    pub fn delete<F>(&mut self, path: String, handler_func: F)
+    where
+        F: Fn(&HttpRequest) -> HttpResponse + Send + Sync + 'static,
+    {
+        self.router
+            .borrow_mut()
+            .delete(format!("{}{}", self.pre_path, path), handler_func)
+    }
+    pub fn head<F>(&mut self, path: String, handler_func: F)
+    where
+        F: Fn(&HttpRequest) -> HttpResponse + Send + Sync + 'static,
+    {
+        self.router
+            .borrow_mut()
+            .head(format!("{}{}", self.pre_path, path), handler_func)
+    }
+    pub fn options<F>(&mut self, path: String, handler_func: F)
+    where
+        F: Fn(&HttpRequest) -> HttpResponse + Send + Sync + 'static,
+    {
+        self.router
+            .borrow_mut()
+            .options(format!("{}{}", self.pre_path, path), handler_func)
+    }
+    pub fn any<F>(&mut self, path: String, handler_func: F)
+    where
+        F: Fn(&HttpRequest) -> HttpResponse + Send + Sync + 'static,
+    {
+        self.any_handler(path, handler_func)
+    }
+    pub fn any_handler(&mut self, path: String, handler: impl Handler + 'static) {
+        self.router
+            .borrow_mut()
+            .any_handler(format!("{}{}", self.pre_path, path), handler)
+    }
+    //注册任意方法(包括扩展方法)的路由
+    pub fn route<F>(&mut self, method: Method, path: String, handler_func: F)
+    where
+        F: Fn(&HttpRequest) -> HttpResponse + Send + Sync + 'static,
+    {
+        self.route_handler(method, path, handler_func)
+    }
+    //注册实现了Handler的带状态处理器
+    pub fn route_handler(&mut self, method: Method, path: String, handler: impl Handler + 'static) {
+        self.router.borrow_mut().route_handler(
+            method,
+            format!("{}{}", self.pre_path, path),
+            handler,
+        )
+    }
     //创建路由分组
     pub fn create_group(&self, child_path: String) -> ServerGroup<'a> {
         ServerGroup {
-            socket_addr: &self.socket_addr,
+            socket_addr: self.socket_addr,
             router: self.router.clone(),
             pre_path: format!("{}/{}", self.pre_path, child_path), //获取新的路由服务组前缀
+            limits: self.limits,
+        }
+    }
+}
+//在一个连接上依次处理请求,直到客户端关闭、要求关闭、空闲超时或达到请求数上限
+fn handle_connection(
+    mut stream: TcpStream,
+    router: &RouterMap,
+    pre_path: &str,
+    limits: ConnectionLimits,
+) {
+    if stream
+        .set_read_timeout(Some(limits.keep_alive_timeout))
+        .is_err()
+    {
+        return;
+    }
+    //流水线的多个响应分别写出,关掉Nagle算法避免等待对端的延迟确认
+    let _ = stream.set_nodelay(true);
+    let mut parser = RequestParser::with_max_body_size(limits.max_body_size);
+    let mut read_buf = [0; 2000];
+    for served in 1..=limits.max_requests {
+        //流水线请求可能已经在缓冲区里,先解析缓冲区,不够再从连接读
+        //数据可能分多次到达,读到请求完整为止
+        let mut parsed = parser.feed(&[]);
+        let mut expect_checked = false;
+        let mut req = loop {
+            match parsed {
+                Ok(Some(req)) => break req,
+                Ok(None) => {}
+                Err(err) => {
+                    //请求格式有误,返回错误响应后关闭连接
+                    let res = HttpResponse::builder()
+                        .status(err.status_code())
+                        .text(err.to_string());
+                    reject(&mut stream, res);
+                    return;
+                }
+            }
+            //请求头到了、请求体还没到,先处理Expect
+            if let (false, Some(head)) = (expect_checked, parser.head()) {
+                expect_checked = true;
+                match check_expect(router, pre_path, head) {
+                    Ok(false) => {}
+                    Ok(true) => {
+                        let res = HttpResponse::builder().status(StatusCode::CONTINUE).build();
+                        if res.send_response(&mut stream).is_err() {
+                            return;
+                        }
+                    }
+                    Err(res) => {
+                        reject(&mut stream, res);
+                        return;
+                    }
+                }
+            }
+            match stream.read(&mut read_buf) {
+                Ok(0) | Err(_) => return,
+                Ok(n) => parsed = parser.feed(&read_buf[..n]),
+            }
+        };
+        let keep_alive = served < limits.max_requests && req.is_keep_alive();
+        //按请求到达的顺序逐个处理,响应顺序和请求顺序一致
+        //客户端中途断开时写入会失败,丢弃这个连接即可
+        match router.handle_req(pre_path, &mut req, &mut stream, keep_alive) {
+            Ok(true) => {}
+            Ok(false) => return,
+            Err(err) => {
+                println!("Failed to send response: {}", err);
+                return;
+            }
         }
     }
 }
+//按RFC 9110 10.1.1 处理Expect: 返回Ok(true)表示需要先回复100 Continue,
+//Err是提前拒绝的响应;请求体过大时解析器已经返回了413
+fn check_expect(
+    router: &RouterMap,
+    pre_path: &str,
+    head: &HttpRequest,
+) -> Result<bool, HttpResponse> {
+    let Some(expect) = head.header.get("Expect") else {
+        return Ok(false);
+    };
+    //HTTP/1.0的客户端不认识100 Continue,必须忽略
+    if head.version == Version::V1_0 {
+        return Ok(false);
+    }
+    let expectation_failed = |msg: &str| {
+        HttpResponse::builder()
+            .status(StatusCode::EXPECTATION_FAILED)
+            .text(msg)
+    };
+    if !expect.eq_ignore_ascii_case("100-continue") {
+        return Err(expectation_failed("unsupported expectation"));
+    }
+    //没有路由能处理的请求不必让客户端再发送请求体
+    if !router.has_route(&head.method, format!("{}{}", pre_path, head.path())) {
+        return Err(expectation_failed("no route accepts this request"));
+    }
+    Ok(true)
+}
+//占用一个连接名额,连接处理结束时归还
+struct ConnectionSlot(Arc<AtomicUsize>);
+impl ConnectionSlot {
+    fn acquire(active: &Arc<AtomicUsize>, max_connections: usize) -> Option<Self> {
+        active
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| {
+                (n < max_connections).then_some(n + 1)
+            })
+            .ok()
+            .map(|_| Self(active.clone()))
+    }
+}
+impl Drop for ConnectionSlot {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+//提前拒绝时请求体可能还没读完,回复后关闭连接
+fn reject(stream: &mut TcpStream, mut res: HttpResponse) {
+    res.headers_mut().insert("Connection", "close");
+    let _ = res.send_response(stream);
+}
@@ -0,0 +1,393 @@
+use std::{
+    env,
+    io::{self, Read, Write},
+    net::{SocketAddr, TcpStream, ToSocketAddrs},
+    process,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use http::{
+    http_header::HeaderMap,
+    http_parser::ResponseParser,
+    http_request::{HttpRequest, Method, ParseError, Resource, Version},
+    http_uri::Uri,
+};
+use tcpclient::client::wants_close;
+
+const USAGE: &str = "usage: loadgen [-c CONNECTIONS] [-d SECONDS | -n REQUESTS] [-p DEPTH] [-X METHOD] [--no-keepalive] URL
+
+  -c CONNECTIONS   concurrent connections, defaults to 10
+  -d SECONDS       run for a fixed duration, defaults to 10
+  -n REQUESTS      send a fixed number of requests instead of running for a duration
+  -p DEPTH         pipeline DEPTH requests per write on each connection, defaults to 1
+  -X METHOD        request method, defaults to GET
+  --no-keepalive   open a new connection for every request";
+
+#[derive(Debug, PartialEq)]
+struct Args {
+    connections: usize,
+    duration: Duration,
+    requests: Option<usize>,
+    depth: usize,
+    method: Method,
+    keep_alive: bool,
+    url: String,
+}
+impl Default for Args {
+    fn default() -> Self {
+        Self {
+            connections: 10,
+            duration: Duration::from_secs(10),
+            requests: None,
+            depth: 1,
+            method: Method::GET,
+            keep_alive: true,
+            url: String::new(),
+        }
+    }
+}
+//每条连接各自统计,结束后合并
+#[derive(Debug, Default)]
+struct Stats {
+    latencies: Vec<Duration>,
+    non_2xx: usize,
+    connect_errors: usize,
+    io_errors: usize,
+    timeouts: usize,
+    parse_errors: usize,
+    bytes: usize,
+}
+impl Stats {
+    fn merge(&mut self, other: Stats) {
+        self.latencies.extend(other.latencies);
+        self.non_2xx += other.non_2xx;
+        self.connect_errors += other.connect_errors;
+        self.io_errors += other.io_errors;
+        self.timeouts += other.timeouts;
+        self.parse_errors += other.parse_errors;
+        self.bytes += other.bytes;
+    }
+    fn errors(&self) -> usize {
+        self.connect_errors + self.io_errors + self.timeouts + self.parse_errors
+    }
+    //一次失败会让这一批里还没收到响应的请求都算作失败
+    fn record_failure(&mut self, failure: &Failure, failed: usize) {
+        match failure {
+            Failure::Io(err)
+                if matches!(
+                    err.kind(),
+                    io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+                ) =>
+            {
+                self.timeouts += failed
+            }
+            Failure::Io(_) => self.io_errors += failed,
+            Failure::Parse => self.parse_errors += failed,
+        }
+    }
+}
+//请求数或截止时间两种结束条件
+struct Budget {
+    remaining: Option<AtomicUsize>,
+    deadline: Instant,
+}
+impl Budget {
+    //领取最多want个请求,返回实际领到的个数
+    fn claim(&self, want: usize) -> usize {
+        match &self.remaining {
+            Some(remaining) => remaining
+                .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| {
+                    Some(n.saturating_sub(want))
+                })
+                .map_or(0, |n| n.min(want)),
+            None if Instant::now() < self.deadline => want,
+            None => 0,
+        }
+    }
+    //服务端提前关闭连接时,没发出去的请求还给预算
+    fn release(&self, count: usize) {
+        if let Some(remaining) = &self.remaining {
+            remaining.fetch_add(count, Ordering::SeqCst);
+        }
+    }
+}
+enum Failure {
+    Io(io::Error),
+    Parse,
+}
+impl From<io::Error> for Failure {
+    fn from(value: io::Error) -> Self {
+        Self::Io(value)
+    }
+}
+impl From<ParseError> for Failure {
+    fn from(_: ParseError) -> Self {
+        Self::Parse
+    }
+}
+
+fn main() {
+    if env::args().any(|arg| arg == "-h" || arg == "--help") {
+        println!("{}", USAGE);
+        return;
+    }
+    let args = match parse_args(env::args().skip(1)) {
+        Ok(args) => args,
+        Err(msg) => {
+            eprintln!("{}\n\n{}", msg, USAGE);
+            process::exit(2);
+        }
+    };
+    let (addr, request) = match prepare(&args) {
+        Ok(prepared) => prepared,
+        Err(msg) => {
+            eprintln!("loadgen: {}", msg);
+            process::exit(1);
+        }
+    };
+    let budget = Arc::new(Budget {
+        remaining: args.requests.map(AtomicUsize::new),
+        deadline: Instant::now() + args.duration,
+    });
+    let request = Arc::new(request);
+    let depth = if args.keep_alive { args.depth } else { 1 };
+    let started = Instant::now();
+    let workers: Vec<_> = (0..args.connections)
+        .map(|_| {
+            let budget = budget.clone();
+            let request = request.clone();
+            let method = args.method.clone();
+            let keep_alive = args.keep_alive;
+            thread::spawn(move || run_worker(addr, &request, &method, depth, keep_alive, &budget))
+        })
+        .collect();
+    let mut stats = Stats::default();
+    for worker in workers {
+        stats.merge(worker.join().expect("worker panicked"));
+    }
+    report(&args, &mut stats, started.elapsed());
+}
+fn parse_args(mut iter: impl Iterator<Item = String>) -> Result<Args, String> {
+    let mut args = Args::default();
+    let mut url = None;
+    while let Some(arg) = iter.next() {
+        let mut value = |flag: &str| iter.next().ok_or(format!("{} needs a value", flag));
+        let number = |value: String| {
+            value
+                .parse::<usize>()
+                .ok()
+                .filter(|n| *n > 0)
+                .ok_or(format!("{} needs a positive number, got {:?}", arg, value))
+        };
+        match arg.as_str() {
+            "-c" => args.connections = number(value(&arg)?)?,
+            "-d" => args.duration = Duration::from_secs(number(value(&arg)?)? as u64),
+            "-n" => args.requests = Some(number(value(&arg)?)?),
+            "-p" => args.depth = number(value(&arg)?)?,
+            "-X" => {
+                args.method = Method::from(value(&arg)?.as_str());
+                if args.method == Method::Uninitialized {
+                    return Err("bad method".into());
+                }
+            }
+            "--no-keepalive" => args.keep_alive = false,
+            _ if arg.starts_with('-') => return Err(format!("unknown option: {}", arg)),
+            _ if url.is_some() => return Err(format!("unexpected argument: {}", arg)),
+            _ => url = Some(arg),
+        }
+    }
+    args.url = url.ok_or("missing url")?;
+    Ok(args)
+}
+//解析目标地址,并把请求提前序列化好,每次直接写出这段字节
+fn prepare(args: &Args) -> Result<(SocketAddr, Vec<u8>), String> {
+    let uri = Uri::parse(&args.url).ok_or(format!("invalid url: {:?}", args.url))?;
+    if uri.scheme() != "http" {
+        return Err(format!("unsupported scheme: {}", uri.scheme()));
+    }
+    let host = uri.host().trim_start_matches('[').trim_end_matches(']');
+    let addr = (host, uri.port().unwrap_or(80))
+        .to_socket_addrs()
+        .map_err(|err| err.to_string())?
+        .next()
+        .ok_or(format!("cannot resolve {}", uri.host()))?;
+    let mut header = HeaderMap::new();
+    header.insert("Host", uri.authority());
+    header.insert("User-Agent", concat!("loadgen/", env!("CARGO_PKG_VERSION")));
+    if !args.keep_alive {
+        header.insert("Connection", "close");
+    }
+    let request = HttpRequest {
+        method: args.method.clone(),
+        version: Version::V1_1,
+        resource: Resource::origin_form(&uri),
+        header,
+        body: Vec::new(),
+        params: Vec::new(),
+    };
+    let mut raw = Vec::new();
+    request.write_to(&mut raw).map_err(|err| err.to_string())?;
+    Ok((addr, raw))
+}
+fn run_worker(
+    addr: SocketAddr,
+    request: &[u8],
+    method: &Method,
+    depth: usize,
+    keep_alive: bool,
+    budget: &Budget,
+) -> Stats {
+    let mut stats = Stats::default();
+    let batch_bytes = request.repeat(depth);
+    'connect: loop {
+        let mut stream = match TcpStream::connect_timeout(&addr, Duration::from_secs(5)) {
+            Ok(stream) => stream,
+            Err(_) => {
+                stats.connect_errors += 1;
+                if budget.claim(1) == 0 {
+                    return stats;
+                }
+                thread::sleep(Duration::from_millis(10));
+                continue;
+            }
+        };
+        let _ = stream.set_read_timeout(Some(Duration::from_secs(5)));
+        let _ = stream.set_nodelay(true);
+        loop {
+            let batch = budget.claim(depth);
+            if batch == 0 {
+                return stats;
+            }
+            let start = Instant::now();
+            let answered = stats.latencies.len();
+            let result = stream
+                .write_all(&batch_bytes[..request.len() * batch])
+                .map_err(Failure::from)
+                .and_then(|_| read_responses(&mut stream, method, batch, start, &mut stats));
+            match result {
+                Ok(close) if keep_alive && !close => {}
+                Ok(_) => {
+                    budget.release(batch - (stats.latencies.len() - answered));
+                    continue 'connect;
+                }
+                Err(failure) => {
+                    let failed = batch - (stats.latencies.len() - answered);
+                    stats.record_failure(&failure, failed);
+                    continue 'connect;
+                }
+            }
+        }
+    }
+}
+//按顺序读回batch个响应,返回服务端是否要求关闭连接
+fn read_responses(
+    stream: &mut TcpStream,
+    method: &Method,
+    batch: usize,
+    start: Instant,
+    stats: &mut Stats,
+) -> Result<bool, Failure> {
+    let mut parser = ResponseParser::for_method(method);
+    let mut buf = [0u8; 16 * 1024];
+    let mut pending = Vec::new();
+    for _ in 0..batch {
+        let res = loop {
+            //上一次读到的数据里可能已经有下一个响应
+            if let Some(res) = parser.feed(&pending)? {
+                pending.clear();
+                break res;
+            }
+            pending.clear();
+            let n = stream.read(&mut buf)?;
+            stats.bytes += n;
+            if n == 0 {
+                match parser.finish()? {
+                    Some(res) => break res,
+                    None => return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into()),
+                }
+            }
+            pending.extend_from_slice(&buf[..n]);
+        };
+        stats.latencies.push(start.elapsed());
+        if !res.status_code.is_success() {
+            stats.non_2xx += 1;
+        }
+        if wants_close(&res) {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+//最近秩法求百分位,latencies需要已经排好序
+fn percentile(latencies: &[Duration], p: f64) -> Duration {
+    if latencies.is_empty() {
+        return Duration::ZERO;
+    }
+    let rank = (p / 100.0 * latencies.len() as f64).ceil() as usize;
+    latencies[rank.clamp(1, latencies.len()) - 1]
+}
+fn report(args: &Args, stats: &mut Stats, elapsed: Duration) {
+    stats.latencies.sort();
+    let completed = stats.latencies.len();
+    let secs = elapsed.as_secs_f64().max(f64::EPSILON);
+    let ms = |d: Duration| d.as_secs_f64() * 1000.0;
+    println!(
+        "{} connections, pipeline depth {}, keep-alive {}",
+        args.connections,
+        args.depth,
+        if args.keep_alive { "on" } else { "off" }
+    );
+    println!(
+        "{} requests in {:.2}s, {} bytes read",
+        completed, secs, stats.bytes
+    );
+    println!(
+        "throughput: {:.2} req/s, {:.2} KiB/s",
+        completed as f64 / secs,
+        stats.bytes as f64 / 1024.0 / secs
+    );
+    println!(
+        "errors: {} (connect {}, io {}, timeout {}, parse {}), non-2xx responses: {}",
+        stats.errors(),
+        stats.connect_errors,
+        stats.io_errors,
+        stats.timeouts,
+        stats.parse_errors,
+        stats.non_2xx
+    );
+    println!(
+        "latency ms: p50 {:.3}, p90 {:.3}, p99 {:.3}, max {:.3}",
+        ms(percentile(&stats.latencies, 50.0)),
+        ms(percentile(&stats.latencies, 90.0)),
+        ms(percentile(&stats.latencies, 99.0)),
+        ms(stats.latencies.last().copied().unwrap_or_default())
+    );
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn test_percentile() {
+        let latencies: Vec<_> = (1..=100).map(Duration::from_millis).collect();
+        assert_eq!(percentile(&latencies, 50.0), Duration::from_millis(50));
+        assert_eq!(percentile(&latencies, 99.0), Duration::from_millis(99));
+        assert_eq!(percentile(&latencies, 100.0), Duration::from_millis(100));
+        assert_eq!(percentile(&latencies[..1], 90.0), Duration::from_millis(1));
+        assert_eq!(percentile(&[], 50.0), Duration::ZERO);
+    }
+    #[test]
+    fn test_budget_claim() {
+        let budget = Budget {
+            remaining: Some(AtomicUsize::new(5)),
+            deadline: Instant::now(),
+        };
+        assert_eq!(budget.claim(4), 4);
+        assert_eq!(budget.claim(4), 1);
+        assert_eq!(budget.claim(4), 0);
+    }
+}
@@ -0,0 +1,597 @@
+use std::{
+    collections::HashMap,
+    error::Error,
+    fmt,
+    io::{self, Read},
+    net::{TcpStream, ToSocketAddrs},
+    sync::Mutex,
+    time::Duration,
+};
+
+use http::{
+    http_header::HeaderMap,
+    http_parser::{ParsedResponse, ResponseParser},
+    http_request::{HttpRequest, Method, ParseError, Resource, Version},
+    http_response::StatusCode,
+    http_uri::Uri,
+};
+use serde::{de::DeserializeOwned, Serialize};
+
+const USER_AGENT: &str = concat!("tcpclient/", env!("CARGO_PKG_VERSION"));
+
+#[derive(Debug)]
+pub enum ClientError {
+    InvalidUrl(String),
+    UnsupportedScheme(String),
+    Io(io::Error),
+    Parse(ParseError),
+    TooManyRedirects(usize),
+    Status(StatusCode),
+    Json(serde_json::Error),
+}
+impl fmt::Display for ClientError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidUrl(url) => write!(f, "invalid url: {:?}", url),
+            Self::UnsupportedScheme(scheme) => write!(f, "unsupported scheme: {}", scheme),
+            Self::Io(err) => write!(f, "io error: {}", err),
+            Self::Parse(err) => write!(f, "bad response: {}", err),
+            Self::TooManyRedirects(max) => write!(f, "more than {} redirects", max),
+            Self::Status(code) => write!(f, "unexpected status: {}", code),
+            Self::Json(err) => write!(f, "json error: {}", err),
+        }
+    }
+}
+impl Error for ClientError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Io(err) => Some(err),
+            Self::Parse(err) => Some(err),
+            Self::Json(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+impl From<io::Error> for ClientError {
+    fn from(value: io::Error) -> Self {
+        Self::Io(value)
+    }
+}
+impl From<ParseError> for ClientError {
+    fn from(value: ParseError) -> Self {
+        Self::Parse(value)
+    }
+}
+impl From<serde_json::Error> for ClientError {
+    fn from(value: serde_json::Error) -> Self {
+        Self::Json(value)
+    }
+}
+
+//一次请求过程中发生的事件,跟随重定向时每一跳都会有一对
+#[derive(Debug)]
+pub enum Exchange<'a> {
+    Request(&'a HttpRequest),     //即将发出的请求,和写到连接上的内容一致
+    Response(&'a ParsedResponse), //收到的响应
+}
+
+//客户端配置,max_redirects为0时不跟随重定向
+#[derive(Debug, Clone)]
+pub struct ClientBuilder {
+    connect_timeout: Option<Duration>,
+    timeout: Option<Duration>,
+    max_redirects: usize,
+    keep_alive: bool,
+}
+impl Default for ClientBuilder {
+    fn default() -> Self {
+        Self {
+            connect_timeout: Some(Duration::from_secs(10)),
+            timeout: Some(Duration::from_secs(30)),
+            max_redirects: 10,
+            keep_alive: true,
+        }
+    }
+}
+impl ClientBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = Some(timeout);
+        self
+    }
+    //每次读写的超时时间
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+    pub fn max_redirects(mut self, max_redirects: usize) -> Self {
+        self.max_redirects = max_redirects;
+        self
+    }
+    pub fn keep_alive(mut self, keep_alive: bool) -> Self {
+        self.keep_alive = keep_alive;
+        self
+    }
+    pub fn build(self) -> Client {
+        Client {
+            config: self,
+            pool: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+//阻塞式HTTP/1.1客户端,空闲连接按host:port保存下来复用
+#[derive(Debug)]
+pub struct Client {
+    config: ClientBuilder,
+    pool: Mutex<HashMap<String, Vec<TcpStream>>>,
+}
+impl Default for Client {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+impl Client {
+    pub fn new() -> Self {
+        ClientBuilder::new().build()
+    }
+    pub fn builder() -> ClientBuilder {
+        ClientBuilder::new()
+    }
+    pub fn get(&self, url: &str) -> Result<ParsedResponse, ClientError> {
+        self.request(Method::GET, url).send()
+    }
+    pub fn post(&self, url: &str, body: impl Into<Vec<u8>>) -> Result<ParsedResponse, ClientError> {
+        self.request(Method::POST, url).body(body).send()
+    }
+    //GET并把2xx响应体按json反序列化
+    pub fn get_json<T: DeserializeOwned>(&self, url: &str) -> Result<T, ClientError> {
+        json_body(self.get(url)?)
+    }
+    //以json发送请求体,并把2xx响应体按json反序列化
+    pub fn post_json<T: Serialize + ?Sized, R: DeserializeOwned>(
+        &self,
+        url: &str,
+        value: &T,
+    ) -> Result<R, ClientError> {
+        json_body(self.request(Method::POST, url).json(value).send()?)
+    }
+    pub fn request(&self, method: Method, url: &str) -> RequestBuilder<'_> {
+        RequestBuilder {
+            client: self,
+            method,
+            url: url.to_string(),
+            headers: HeaderMap::new(),
+            body: Ok(Vec::new()),
+        }
+    }
+    //发送请求并按配置跟随重定向,请求目标必须是absolute形式
+    pub fn execute(&self, req: HttpRequest) -> Result<ParsedResponse, ClientError> {
+        self.execute_with(req, |_| {})
+    }
+    //和execute一样,每一跳的请求和响应都交给observe,用于打印调试信息
+    pub fn execute_with(
+        &self,
+        mut req: HttpRequest,
+        mut observe: impl FnMut(Exchange<'_>),
+    ) -> Result<ParsedResponse, ClientError> {
+        let mut redirects = 0;
+        loop {
+            let res = self.send_once(&req, &mut observe)?;
+            observe(Exchange::Response(&res));
+            let location = match res.headers.get("Location") {
+                Some(location) if is_redirect(res.status_code) => location,
+                _ => return Ok(res),
+            };
+            if self.config.max_redirects == 0 {
+                return Ok(res);
+            }
+            if redirects == self.config.max_redirects {
+                return Err(ClientError::TooManyRedirects(redirects));
+            }
+            redirects += 1;
+            req = redirect_request(req, res.status_code, location)?;
+        }
+    }
+    fn send_once(
+        &self,
+        req: &HttpRequest,
+        observe: &mut impl FnMut(Exchange<'_>),
+    ) -> Result<ParsedResponse, ClientError> {
+        let Resource::Absolute(uri) = &req.resource else {
+            return Err(ClientError::InvalidUrl(req.resource.target()));
+        };
+        //发给源服务器时使用origin形式的请求目标
+        let mut wire = HttpRequest {
+            method: req.method.clone(),
+            version: Version::V1_1,
+            resource: Resource::origin_form(uri),
+            header: req.header.clone(),
+            body: req.body.clone(),
+            params: Vec::new(),
+        };
+        if !self.config.keep_alive {
+            wire.header.insert("Connection", "close");
+        }
+        observe(Exchange::Request(&wire));
+        let key = pool_key(uri);
+        let pooled = self.pool.lock().unwrap().get_mut(&key).and_then(Vec::pop);
+        if let Some(stream) = pooled {
+            //空闲连接可能已经被服务端关掉,一个字节都没收到就断开时才换一条新连接重发
+            //超时等错误时服务端可能已经处理了请求,重发会让请求执行两次
+            let mut received = false;
+            match self.round_trip(stream, &wire, &key, &mut received) {
+                Err(ClientError::Io(err))
+                    if !received && is_stale_connection(&err) && is_idempotent(&wire.method) => {}
+                result => return result,
+            }
+        }
+        let stream = self.connect(uri)?;
+        self.round_trip(stream, &wire, &key, &mut false)
+    }
+    //received记录是否已经收到了响应的数据
+    fn round_trip(
+        &self,
+        mut stream: TcpStream,
+        req: &HttpRequest,
+        key: &str,
+        received: &mut bool,
+    ) -> Result<ParsedResponse, ClientError> {
+        req.write_to(&mut stream)?;
+        let mut parser = ResponseParser::for_method(&req.method);
+        let mut buf = [0u8; 4096];
+        let (res, complete) = loop {
+            let n = stream.read(&mut buf)?;
+            *received |= n > 0;
+            if n == 0 {
+                match parser.finish()? {
+                    Some(res) => break (res, false),
+                    None => {
+                        return Err(io::Error::new(
+                            io::ErrorKind::UnexpectedEof,
+                            "connection closed before response",
+                        )
+                        .into())
+                    }
+                }
+            }
+            if let Some(res) = parser.feed(&buf[..n])? {
+                break (res, true);
+            }
+        };
+        //响应按长度定界且服务端没有要求关闭时才放回连接池
+        if complete && self.config.keep_alive && !wants_close(&res) {
+            self.pool
+                .lock()
+                .unwrap()
+                .entry(key.to_string())
+                .or_default()
+                .push(stream);
+        }
+        Ok(res)
+    }
+    fn connect(&self, uri: &Uri) -> Result<TcpStream, ClientError> {
+        let host = uri.host().trim_start_matches('[').trim_end_matches(']');
+        let mut last_err = None;
+        for addr in (host, uri.port().unwrap_or(80)).to_socket_addrs()? {
+            let stream = match self.config.connect_timeout {
+                Some(timeout) => TcpStream::connect_timeout(&addr, timeout),
+                None => TcpStream::connect(addr),
+            };
+            match stream {
+                Ok(stream) => {
+                    stream.set_read_timeout(self.config.timeout)?;
+                    stream.set_write_timeout(self.config.timeout)?;
+                    stream.set_nodelay(true)?;
+                    return Ok(stream);
+                }
+                Err(err) => last_err = Some(err),
+            }
+        }
+        Err(last_err
+            .unwrap_or_else(|| io::Error::new(io::ErrorKind::NotFound, "host not found"))
+            .into())
+    }
+}
+
+//逐步构造请求,send时发送
+pub struct RequestBuilder<'a> {
+    client: &'a Client,
+    method: Method,
+    url: String,
+    headers: HeaderMap,
+    body: Result<Vec<u8>, ClientError>,
+}
+impl RequestBuilder<'_> {
+    pub fn header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.append(name, value);
+        self
+    }
+    pub fn body(mut self, body: impl Into<Vec<u8>>) -> Self {
+        self.body = Ok(body.into());
+        self
+    }
+    pub fn json<T: Serialize + ?Sized>(mut self, value: &T) -> Self {
+        self.body = serde_json::to_vec(value).map_err(ClientError::from);
+        if !self.headers.contains_key("Content-Type") {
+            self.headers.insert("Content-Type", "application/json");
+        }
+        self
+    }
+    //生成absolute形式的请求,补上Host、User-Agent和Accept
+    pub fn build(self) -> Result<HttpRequest, ClientError> {
+        let uri = Uri::parse(&self.url).ok_or_else(|| ClientError::InvalidUrl(self.url.clone()))?;
+        if uri.scheme() != "http" {
+            return Err(ClientError::UnsupportedScheme(uri.scheme().to_string()));
+        }
+        let mut header = self.headers;
+        if !header.contains_key("Host") {
+            header.insert("Host", host_header(&uri));
+        }
+        if !header.contains_key("User-Agent") {
+            header.insert("User-Agent", USER_AGENT);
+        }
+        if !header.contains_key("Accept") {
+            header.insert("Accept", "*/*");
+        }
+        Ok(HttpRequest {
+            method: self.method,
+            version: Version::V1_1,
+            resource: Resource::Absolute(uri),
+            header,
+            body: self.body?,
+            params: Vec::new(),
+        })
+    }
+    pub fn send(self) -> Result<ParsedResponse, ClientError> {
+        let client = self.client;
+        client.execute(self.build()?)
+    }
+}
+fn json_body<T: DeserializeOwned>(res: ParsedResponse) -> Result<T, ClientError> {
+    if !res.status_code.is_success() {
+        return Err(ClientError::Status(res.status_code));
+    }
+    Ok(res.json()?)
+}
+//复用的连接已经被对端关掉时的错误
+fn is_stale_connection(err: &io::Error) -> bool {
+    matches!(
+        err.kind(),
+        io::ErrorKind::UnexpectedEof
+            | io::ErrorKind::ConnectionReset
+            | io::ErrorKind::ConnectionAborted
+            | io::ErrorKind::BrokenPipe
+    )
+}
+//RFC 9110 9.2.2 幂等的方法重发不会改变结果
+fn is_idempotent(method: &Method) -> bool {
+    matches!(
+        method,
+        Method::GET | Method::HEAD | Method::PUT | Method::DELETE | Method::OPTIONS | Method::TRACE
+    )
+}
+fn is_redirect(code: StatusCode) -> bool {
+    [
+        StatusCode::MOVED_PERMANENTLY,
+        StatusCode::FOUND,
+        StatusCode::SEE_OTHER,
+        StatusCode::TEMPORARY_REDIRECT,
+        StatusCode::PERMANENT_REDIRECT,
+    ]
+    .contains(&code)
+}
+//按RFC 9110 15.4 生成重定向后的请求
+fn redirect_request(
+    mut req: HttpRequest,
+    code: StatusCode,
+    location: &str,
+) -> Result<HttpRequest, ClientError> {
+    let Resource::Absolute(base) = &req.resource else {
+        return Err(ClientError::InvalidUrl(req.resource.target()));
+    };
+    let target = resolve(base, location).ok_or_else(|| ClientError::InvalidUrl(location.into()))?;
+    if target.scheme() != "http" {
+        return Err(ClientError::UnsupportedScheme(target.scheme().to_string()));
+    }
+    //303以及POST上的301/302改成不带请求体的GET
+    let to_get = (code == StatusCode::SEE_OTHER && req.method != Method::HEAD)
+        || ((code == StatusCode::MOVED_PERMANENTLY || code == StatusCode::FOUND)
+            && req.method == Method::POST);
+    if to_get {
+        req.method = Method::GET;
+        req.body.clear();
+        req.header.remove("Content-Type");
+    }
+    //换了主机就不再带上凭据
+    if target.authority() != base.authority() {
+        req.header.remove("Authorization");
+        req.header.remove("Cookie");
+        req.header.insert("Host", host_header(&target));
+    }
+    req.resource = Resource::Absolute(target);
+    Ok(req)
+}
+//把Location解析成绝对URI,支持绝对、协议相对、绝对路径和相对路径几种写法
+fn resolve(base: &Uri, location: &str) -> Option<Uri> {
+    if let Some(uri) = Uri::parse(location) {
+        return Some(uri);
+    }
+    let absolute = if location.starts_with("//") {
+        format!("{}:{}", base.scheme(), location)
+    } else if location.starts_with('/') {
+        format!("{}://{}{}", base.scheme(), base.authority(), location)
+    } else {
+        let base_path = base.path_and_query();
+        let base_path = base_path.split('?').next().unwrap_or("/");
+        let dir = base_path.rsplit_once('/').map_or("", |(dir, _)| dir);
+        format!(
+            "{}://{}{}/{}",
+            base.scheme(),
+            base.authority(),
+            dir,
+            location
+        )
+    };
+    Uri::parse(&absolute)
+}
+//Host字段不带用户信息,默认端口省略
+fn host_header(uri: &Uri) -> String {
+    match uri.port() {
+        Some(port) if port != 80 => format!("{}:{}", uri.host(), port),
+        _ => uri.host().to_string(),
+    }
+}
+fn pool_key(uri: &Uri) -> String {
+    format!("{}:{}", uri.host(), uri.port().unwrap_or(80))
+}
+//响应之后连接是否不能再用
+//1.1默认保持连接,1.0只有服务端明确回复keep-alive才保持
+pub fn wants_close(res: &ParsedResponse) -> bool {
+    let keep_alive = match res.version {
+        Version::V1_0 => res.headers.has_token("Connection", "keep-alive"),
+        _ => !res.headers.has_token("Connection", "close"),
+    };
+    !keep_alive || res.status_code == StatusCode::SWITCHING_PROTOCOLS
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use http::http_parser::RequestParser;
+    use std::{io::Write, net::TcpListener, thread};
+
+    //只接受一条连接,依次读请求并回复给定的响应
+    fn serve(responses: Vec<&'static str>) -> (String, thread::JoinHandle<Vec<HttpRequest>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        let handle = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut requests = Vec::new();
+            let mut buf = [0u8; 1024];
+            for response in responses {
+                let mut parser = RequestParser::new();
+                let req = loop {
+                    let n = stream.read(&mut buf).unwrap();
+                    if let Some(req) = parser.feed(&buf[..n]).unwrap() {
+                        break req;
+                    }
+                };
+                requests.push(req);
+                stream.write_all(response.as_bytes()).unwrap();
+            }
+            requests
+        });
+        (addr, handle)
+    }
+    fn test_client() -> Client {
+        Client::builder().timeout(Duration::from_secs(2)).build()
+    }
+    #[test]
+    fn test_keep_alive_reuses_connection() {
+        let (addr, handle) = serve(vec![
+            "HTTP/1.1 200 OK\r\nContent-Length: 5\r\n\r\nfirst",
+            "HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n6\r\nsecond\r\n0\r\n\r\n",
+        ]);
+        let client = test_client();
+        let res = client.get(&format!("http://{}/one%2Fa", addr)).unwrap();
+        assert_eq!(res.text().unwrap(), "first");
+        let res = client
+            .post(&format!("http://{}/two?x=1", addr), "data")
+            .unwrap();
+        assert_eq!(res.text().unwrap(), "second");
+        let requests = handle.join().unwrap();
+        //原始编码原样发送,%2F不会被解码成/
+        assert_eq!(requests[0].resource.target(), "/one%2Fa");
+        assert_eq!(requests[0].path(), "/one/a");
+        assert_eq!(requests[0].header.get("Host"), Some(addr.as_str()));
+        assert_eq!(requests[1].method, Method::POST);
+        assert_eq!(requests[1].query("x"), Some("1"));
+        assert_eq!(requests[1].body, b"data");
+    }
+    #[test]
+    fn test_follow_redirect() {
+        let (addr, handle) = serve(vec![
+            "HTTP/1.1 302 Found\r\nLocation: next?step=2\r\nContent-Length: 0\r\n\r\n",
+            "HTTP/1.1 200 OK\r\nContent-Length: 11\r\n\r\n{\"id\": 42 }",
+        ]);
+        let client = test_client();
+        let value: serde_json::Value = client
+            .post_json(&format!("http://{}/api/start", addr), &[1, 2])
+            .unwrap();
+        assert_eq!(value["id"], 42);
+        let requests = handle.join().unwrap();
+        assert_eq!(requests[0].text().unwrap(), "[1,2]");
+        assert_eq!(requests[1].method, Method::GET);
+        assert_eq!(requests[1].path(), "/api/next");
+        assert!(requests[1].body.is_empty());
+    }
+    #[test]
+    fn test_execute_with_reports_each_hop() {
+        let (addr, handle) = serve(vec![
+            "HTTP/1.1 301 Moved Permanently\r\nLocation: /b\r\nContent-Length: 0\r\n\r\n",
+            "HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nok",
+        ]);
+        let client = test_client();
+        let req = client
+            .request(Method::GET, &format!("http://{}/a", addr))
+            .build()
+            .unwrap();
+        let mut hops = Vec::new();
+        client
+            .execute_with(req, |exchange| match exchange {
+                Exchange::Request(req) => hops.push(req.resource.target()),
+                Exchange::Response(res) => hops.push(res.status_code.to_string()),
+            })
+            .unwrap();
+        handle.join().unwrap();
+        assert_eq!(hops[0], "/a");
+        assert_eq!(hops[2], "/b");
+        assert_eq!(hops.len(), 4);
+    }
+    #[test]
+    fn test_no_retry_after_timeout() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        let handle = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut buf = [0u8; 1024];
+            let mut requests = 0;
+            //第一个请求正常回复,连接进入连接池
+            let _ = stream.read(&mut buf).unwrap();
+            requests += 1;
+            stream
+                .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nok")
+                .unwrap();
+            //第二个请求等到客户端超时以后也不回复
+            let _ = stream.read(&mut buf).unwrap();
+            requests += 1;
+            thread::sleep(Duration::from_millis(600));
+            listener.set_nonblocking(true).unwrap();
+            while listener.accept().is_ok() {
+                requests += 1;
+            }
+            requests
+        });
+        let client = Client::builder()
+            .timeout(Duration::from_millis(200))
+            .build();
+        let url = format!("http://{}/slow", addr);
+        assert!(client.get(&url).is_ok());
+        assert!(matches!(client.get(&url), Err(ClientError::Io(_))));
+        assert_eq!(handle.join().unwrap(), 2);
+    }
+    #[test]
+    fn test_invalid_url() {
+        let client = Client::new();
+        assert!(matches!(
+            client.get("localhost/path"),
+            Err(ClientError::InvalidUrl(_))
+        ));
+        assert!(matches!(
+            client.get("https://localhost/"),
+            Err(ClientError::UnsupportedScheme(_))
+        ));
+    }
+}
@@ -1,12 +1,183 @@
 use std::{
-    io::{Read, Write},
-    net::TcpStream,
+    env, fs,
+    io::{self, Write},
+    process,
 };
 
+use http::{
+    http_parser::ParsedResponse,
+    http_request::{HttpRequest, Method},
+};
+use tcpclient::client::{Client, Exchange};
+
+const USAGE: &str =
+    "usage: tcpclient [-X METHOD] [-H 'Name: value']... [-d DATA|@FILE] [-L] [-v] URL
+
+  -X METHOD   request method, defaults to GET (POST when -d is given)
+  -H HEADER   extra request header, can be repeated
+  -d DATA     request body, @FILE reads the body from a file
+  -L          follow redirects
+  -v          print the request and response heads to stderr
+
+exit code: 0 for 1xx-3xx, 4 for 4xx, 5 for 5xx, 1 on errors, 2 on bad arguments";
+
+#[derive(Debug, Default, PartialEq)]
+struct Args {
+    method: Option<String>,
+    headers: Vec<(String, String)>,
+    data: Option<String>,
+    follow: bool,
+    verbose: bool,
+    url: String,
+}
+
 fn main() {
-    let mut stream = TcpStream::connect("127.0.0.1:3000").unwrap();
-    stream.write("hello".as_bytes()).unwrap();
-    let mut buffer = [0; 5];
-    stream.read(&mut buffer).unwrap();
-    println!("{:?}", String::from_utf8((&buffer).to_vec()).unwrap());
+    if env::args().any(|arg| arg == "-h" || arg == "--help") {
+        println!("{}", USAGE);
+        return;
+    }
+    let args = match parse_args(env::args().skip(1)) {
+        Ok(args) => args,
+        Err(msg) => {
+            eprintln!("{}\n\n{}", msg, USAGE);
+            process::exit(2);
+        }
+    };
+    match run(&args) {
+        Ok(res) => process::exit(exit_code(&res)),
+        Err(msg) => {
+            eprintln!("tcpclient: {}", msg);
+            process::exit(1);
+        }
+    }
+}
+fn parse_args(mut iter: impl Iterator<Item = String>) -> Result<Args, String> {
+    let mut args = Args::default();
+    let mut url = None;
+    while let Some(arg) = iter.next() {
+        let mut value = |flag: &str| iter.next().ok_or(format!("{} needs a value", flag));
+        match arg.as_str() {
+            "-X" | "--request" => args.method = Some(value(&arg)?),
+            "-H" | "--header" => {
+                let header = value(&arg)?;
+                let (name, val) = header
+                    .split_once(':')
+                    .ok_or(format!("bad header: {:?}", header))?;
+                args.headers.push((name.trim().into(), val.trim().into()));
+            }
+            "-d" | "--data" => args.data = Some(value(&arg)?),
+            "-L" | "--location" => args.follow = true,
+            "-v" | "--verbose" => args.verbose = true,
+            _ if arg.starts_with('-') => return Err(format!("unknown option: {}", arg)),
+            _ if url.is_some() => return Err(format!("unexpected argument: {}", arg)),
+            _ => url = Some(arg),
+        }
+    }
+    args.url = url.ok_or("missing url")?;
+    Ok(args)
+}
+fn run(args: &Args) -> Result<ParsedResponse, String> {
+    let method = match &args.method {
+        Some(method) => Method::from(method.as_str()),
+        None if args.data.is_some() => Method::POST,
+        None => Method::GET,
+    };
+    if method == Method::Uninitialized {
+        return Err(format!(
+            "bad method: {:?}",
+            args.method.as_deref().unwrap_or("")
+        ));
+    }
+    let client = Client::builder()
+        .max_redirects(if args.follow { 10 } else { 0 })
+        .build();
+    let mut builder = client.request(method, &args.url);
+    for (name, val) in &args.headers {
+        builder = builder.header(name.as_str(), val.as_str());
+    }
+    if let Some(data) = &args.data {
+        let body = match data.strip_prefix('@') {
+            Some(path) => fs::read(path).map_err(|err| format!("{}: {}", path, err))?,
+            None => data.clone().into_bytes(),
+        };
+        builder = builder.body(body);
+    }
+    let req = builder.build().map_err(|err| err.to_string())?;
+    //跟随重定向时每一跳都打印
+    let res = client
+        .execute_with(req, |exchange| match exchange {
+            _ if !args.verbose => {}
+            Exchange::Request(req) => print_request_head(req),
+            Exchange::Response(res) => print_response_head(res),
+        })
+        .map_err(|err| err.to_string())?;
+    let mut stdout = io::stdout().lock();
+    stdout
+        .write_all(&res.body)
+        .and_then(|_| stdout.flush())
+        .map_err(|err| err.to_string())?;
+    Ok(res)
+}
+//和curl一样,请求头以"> "开头,响应头以"< "开头
+//请求头按实际写到连接上的内容打印
+fn print_request_head(req: &HttpRequest) {
+    let mut raw = Vec::new();
+    if req.write_to(&mut raw).is_err() {
+        return;
+    }
+    let raw = String::from_utf8_lossy(&raw);
+    let head = raw.split("\r\n\r\n").next().unwrap_or_default();
+    for line in head.split("\r\n") {
+        eprintln!("> {}", line);
+    }
+    eprintln!(">");
+}
+fn print_response_head(res: &ParsedResponse) {
+    eprintln!("< {} {} {}", res.version, res.status_code, res.reason);
+    for (name, val) in res.headers.iter() {
+        eprintln!("< {}: {}", name, val);
+    }
+    eprintln!("<");
+}
+fn exit_code(res: &ParsedResponse) -> i32 {
+    if res.status_code.is_client_error() {
+        4
+    } else if res.status_code.is_server_error() {
+        5
+    } else {
+        0
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    fn parse(args: &[&str]) -> Result<Args, String> {
+        parse_args(args.iter().map(|arg| arg.to_string()))
+    }
+    #[test]
+    fn test_parse_args() {
+        let args = parse(&[
+            "-X",
+            "PUT",
+            "-H",
+            "Content-Type: application/json",
+            "-d",
+            "@body.json",
+            "-v",
+            "http://localhost:9977/api",
+        ])
+        .unwrap();
+        assert_eq!(args.method.as_deref(), Some("PUT"));
+        assert_eq!(
+            args.headers,
+            [("Content-Type".to_string(), "application/json".to_string())]
+        );
+        assert_eq!(args.data.as_deref(), Some("@body.json"));
+        assert!(args.verbose && !args.follow);
+        assert_eq!(args.url, "http://localhost:9977/api");
+        assert!(parse(&["-H"]).is_err());
+        assert!(parse(&["-H", "no-colon", "http://x/"]).is_err());
+        assert!(parse(&["-q", "http://x/"]).is_err());
+        assert!(parse(&[]).is_err());
+    }
 }
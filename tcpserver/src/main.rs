@@ -10,7 +10,7 @@ fn main() {
         let mut stream = stream.unwrap();
         println!("connect !");
         let mut buffer = [0; 1024];
-        stream.read(&mut buffer).unwrap();
-        stream.write(&buffer).unwrap();
+        let n = stream.read(&mut buffer).unwrap();
+        stream.write_all(&buffer[..n]).unwrap();
     }
 }